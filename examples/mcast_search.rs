@@ -1,7 +1,7 @@
 extern crate ssdp;
 
-use ssdp::header::{HeaderMut, Man, MX, ST};
-use ssdp::message::{SearchRequest, Multicast};
+use ssdp::header::{HeaderMut, HeaderRef, Location, Man, MX, ST};
+use ssdp::message::{SearchRequest, SearchResponse, Multicast};
 
 fn main() {
     // Create Our Search Request
@@ -14,6 +14,16 @@ fn main() {
 
     // Iterate Over Streaming Responses
     for (msg, src) in request.multicast().unwrap() {
-        println!("Received The Following Message From {}:\n{:?}\n\n", src, msg);
+        println!("Received The Following Message From {}:", src);
+        handle(msg);
     }
 }
+
+// Responses Can Be Named Outside Of The Crate
+fn handle(r: SearchResponse) {
+    if let Some(&Location(ref location)) = r.get::<Location>() {
+        println!("Device Description At {}", location);
+    }
+
+    println!("{:?}\n\n", r);
+}
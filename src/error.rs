@@ -1,7 +1,31 @@
+use std::fmt::{self, Display, Formatter};
 use std::io;
 use std::net;
 use hyper;
 
+use message::MessageType;
+
+/// Enumerates the components of a message that can fail to parse.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ParseComponent {
+    /// The request or status line.
+    StartLine,
+    /// A header line.
+    Header,
+    /// The empty line terminating the headers.
+    Terminator,
+}
+
+impl Display for ParseComponent {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            ParseComponent::StartLine => f.write_str("start line"),
+            ParseComponent::Header => f.write_str("header"),
+            ParseComponent::Terminator => f.write_str("terminator"),
+        }
+    }
+}
+
 /// Enumerates all errors that can occur when dealing with an SSDP message.
 error_chain! {
 
@@ -17,15 +41,22 @@ error_chain! {
             description("invalid HTTP")
             display("invalid HTTP message: '{:?}'", message)
         }
-        /// Message did not specify HTTP/1.1 as version.
-        InvalidHttpVersion { }
-        /// Message consists of an error code.
+        /// Message could not be parsed.
         ///
-        /// Error code is supplied.
-        ResponseCode(code:u16) {
-            description("HTTP Error response")
-            display("HTTP Error response: {}", code)
+        /// Failing component and the byte offset it starts at are supplied.
+        Parse(component: ParseComponent, offset: usize) {
+            description("malformed SSDP message")
+            display("malformed SSDP message: invalid {} at byte {}", component, offset)
         }
+        /// Message is valid but of a different type than was expected.
+        ///
+        /// Expected and received message types are supplied.
+        WrongMessageType(expected: MessageType, found: MessageType) {
+            description("wrong message type")
+            display("expected a {:?} message, found a {:?} message", expected, found)
+        }
+        /// Message did not specify HTTP/1.1 as version.
+        InvalidHttpVersion { }
         /// Method supplied is not a valid SSDP method.
         ///
         /// Method received is supplied.
@@ -47,6 +78,27 @@ error_chain! {
             description("missing header")
             display("missing header: '{}'", header)
         }
+        /// Outgoing message does not fit in a single datagram.
+        ///
+        /// Serialized size of the message is supplied.
+        MessageTooLarge(size: usize) {
+            description("message too large")
+            display("message of {} bytes exceeds the maximum SSDP message size", size)
+        }
+        /// Header value contains a line break, which would inject headers.
+        ///
+        /// Header name is supplied.
+        HeaderInjection(header: String) {
+            description("header injection")
+            display("header value contains a line break: '{}'", header)
+        }
+        /// No local interface has the requested address.
+        ///
+        /// Requested address is supplied.
+        UnknownInterface(addr: net::IpAddr) {
+            description("unknown interface")
+            display("no local interface has the address {}", addr)
+        }
         /// Header has an invalid value.
         ///
         /// Header name with error message are supplied.
@@ -0,0 +1,67 @@
+use std::fmt::{Formatter, Result};
+
+use hyper::error::{self, Error};
+use hyper::header::{HeaderFormat, Header};
+
+const CPFN_HEADER_NAME: &str = "CPFN.UPNP.ORG";
+
+/// Represents a header used by control points to specify their friendly name.
+///
+/// Sent with search requests so that devices can tell control points apart.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CPFN(pub String);
+
+impl Header for CPFN {
+    fn header_name() -> &'static str {
+        CPFN_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+        if raw.len() != 1 || raw[0].is_empty() {
+            return Err(Error::Header);
+        }
+
+        let owned_bytes = raw[0].clone();
+
+        match String::from_utf8(owned_bytes) {
+            Ok(n) => Ok(CPFN(n)),
+            Err(_) => Err(Error::Header),
+        }
+    }
+}
+
+impl HeaderFormat for CPFN {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        fmt.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::header::Header;
+
+    use super::CPFN;
+
+    #[test]
+    fn positive_cpfn() {
+        let cpfn_header_value = &[b"Living Room TV"[..].to_vec()];
+
+        assert_eq!(CPFN::parse_header(cpfn_header_value).unwrap(), CPFN("Living Room TV".to_owned()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_empty() {
+        let cpfn_header_value = &[b""[..].to_vec()];
+
+        CPFN::parse_header(cpfn_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_invalid_utf8() {
+        let cpfn_header_value = &[b"Living Room TV\x80"[..].to_vec()];
+
+        CPFN::parse_header(cpfn_header_value).unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+use std::fmt::{Formatter, Result};
+
+use hyper::Url;
+use hyper::error::{self, Error};
+use hyper::header::{HeaderFormat, Header};
+
+const LOCATION_HEADER_NAME: &'static str = "LOCATION";
+
+/// Represents a header used to specify the url of a device's DDD.
+///
+/// Received values must be absolute `http` or `https` urls with a host.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Location(pub String);
+
+impl Location {
+    /// Get the parsed url of the location.
+    ///
+    /// Returns `None` for a locally constructed location that is not a valid url.
+    pub fn url(&self) -> Option<Url> {
+        parse_url(&self.0)
+    }
+}
+
+impl Header for Location {
+    fn header_name() -> &'static str {
+        LOCATION_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+        if raw.len() != 1 {
+            return Err(Error::Header);
+        }
+
+        let value = match String::from_utf8(raw[0].clone()) {
+            Ok(n) => n,
+            Err(_) => return Err(Error::Header),
+        };
+
+        match parse_url(&value) {
+            Some(_) => Ok(Location(value)),
+            None => Err(Error::Header),
+        }
+    }
+}
+
+impl HeaderFormat for Location {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        try!(fmt.write_str(&self.0));
+
+        Ok(())
+    }
+}
+
+/// Parse the value as an absolute `http` or `https` url with a host.
+fn parse_url(value: &str) -> Option<Url> {
+    match Url::parse(value) {
+        Ok(ref url) if url.host_str().is_none_or(str::is_empty) => None,
+        Ok(url) => {
+            match url.scheme() {
+                "http" | "https" => Some(url),
+                _ => None,
+            }
+        }
+        Err(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::header::Header;
+
+    use super::Location;
+
+    #[test]
+    fn positive_http() {
+        let location_header_value = &[b"http://192.168.1.1:8080/description.xml"[..].to_vec()];
+
+        let location = Location::parse_header(location_header_value).unwrap();
+        let url = location.url().unwrap();
+
+        assert_eq!(url.host_str(), Some("192.168.1.1"));
+        assert_eq!(url.port(), Some(8080));
+        assert_eq!(url.path(), "/description.xml");
+    }
+
+    #[test]
+    fn positive_https() {
+        let location_header_value = &[b"https://[fe80::1]/description.xml"[..].to_vec()];
+
+        Location::parse_header(location_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_not_a_url() {
+        let location_header_value = &[b"just some text"[..].to_vec()];
+
+        Location::parse_header(location_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_unsupported_scheme() {
+        let location_header_value = &[b"ftp://192.168.1.1/description.xml"[..].to_vec()];
+
+        Location::parse_header(location_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_missing_host() {
+        let location_header_value = &[b"http://:8080/description.xml"[..].to_vec()];
+
+        Location::parse_header(location_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_multiple_headers() {
+        let location_header_value = &[b"http://192.168.1.1/"[..].to_vec(), b"http://192.168.1.2/"[..].to_vec()];
+
+        Location::parse_header(location_header_value).unwrap();
+    }
+}
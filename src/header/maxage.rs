@@ -0,0 +1,103 @@
+use std::fmt::{Formatter, Result};
+use std::time::Duration;
+
+use hyper::error::{self, Error};
+use hyper::header::{HeaderFormat, Header};
+
+const MAX_AGE_HEADER_NAME: &str = "CACHE-CONTROL";
+
+const MAX_AGE_DIRECTIVE: &str = "max-age";
+
+/// Represents the `max-age` directive of the `CACHE-CONTROL` header, in seconds.
+///
+/// This is how long an advertisement is valid for. Other directives in the
+/// header are ignored, and the header fails to parse if `max-age` is missing.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MaxAge(pub u32);
+
+impl MaxAge {
+    /// Get the time the advertisement is valid for.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(u64::from(self.0))
+    }
+}
+
+impl Header for MaxAge {
+    fn header_name() -> &'static str {
+        MAX_AGE_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+        let directives = raw.iter().flat_map(|line| line.split(|&b| b == b','));
+
+        for directive in directives {
+            let directive = String::from_utf8_lossy(directive);
+            let mut parts = directive.splitn(2, '=');
+
+            let key = parts.next().unwrap_or("").trim();
+            if !key.eq_ignore_ascii_case(MAX_AGE_DIRECTIVE) {
+                continue;
+            }
+
+            let value = parts.next().unwrap_or("").trim().trim_matches('"');
+            return value.parse().map(MaxAge).map_err(|_| Error::Header);
+        }
+
+        Err(Error::Header)
+    }
+}
+
+impl HeaderFormat for MaxAge {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        write!(fmt, "{}={}", MAX_AGE_DIRECTIVE, self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use hyper::header::Header;
+
+    use super::MaxAge;
+
+    #[test]
+    fn positive_max_age() {
+        let max_age_header_value = &[b"max-age=1800"[..].to_vec()];
+
+        let max_age = MaxAge::parse_header(max_age_header_value).unwrap();
+
+        assert_eq!(max_age, MaxAge(1800));
+        assert_eq!(max_age.duration(), Duration::from_secs(1800));
+    }
+
+    #[test]
+    fn positive_extra_directives() {
+        let max_age_header_value = &[b"no-cache,  MAX-AGE = 120 , private"[..].to_vec()];
+
+        assert_eq!(MaxAge::parse_header(max_age_header_value).unwrap(), MaxAge(120));
+    }
+
+    #[test]
+    fn positive_multiple_lines() {
+        let max_age_header_value = &[b"no-cache"[..].to_vec(), b"max-age=\"60\""[..].to_vec()];
+
+        assert_eq!(MaxAge::parse_header(max_age_header_value).unwrap(), MaxAge(60));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_missing_max_age() {
+        let max_age_header_value = &[b"no-cache, private"[..].to_vec()];
+
+        MaxAge::parse_header(max_age_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_invalid_seconds() {
+        let max_age_header_value = &[b"max-age=-5"[..].to_vec()];
+
+        MaxAge::parse_header(max_age_header_value).unwrap();
+    }
+}
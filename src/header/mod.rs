@@ -6,33 +6,63 @@
 
 use std::borrow::Cow;
 use std::fmt::Debug;
+use std::net::SocketAddr;
 
 use hyper::header::{Headers, Header, HeaderFormat};
 
 mod bootid;
 mod configid;
+mod cpfn;
+mod location;
 mod man;
-mod mx;
+mod maxage;
+pub(crate) mod mx;
+mod nextbootid;
 mod nt;
 mod nts;
 mod searchport;
 mod securelocation;
+mod serverinfo;
 mod st;
+mod target;
 mod usn;
 
 pub use self::bootid::BootID;
 pub use self::configid::ConfigID;
+pub use self::cpfn::CPFN;
+pub use self::location::Location;
 pub use self::man::Man;
+pub use self::maxage::MaxAge;
 pub use self::mx::MX;
+pub use self::nextbootid::NextBootID;
 pub use self::nt::NT;
 pub use self::nts::NTS;
 pub use self::searchport::SearchPort;
 pub use self::securelocation::SecureLocation;
+pub use self::serverinfo::ServerInfo;
 pub use self::st::ST;
+pub use self::target::TargetType;
 pub use self::usn::USN;
 
 // Re-exports
-pub use hyper::header::{Location, Server, CacheControl, CacheDirective};
+pub use hyper::header::{Server, UserAgent, CacheControl, CacheDirective, Host};
+
+/// Build the `HOST` header for a message sent to the given address.
+///
+/// IPv6 addresses are put in brackets, followed by the `%` separated scope id
+/// for link-local groups such as `[FF02::C%3]:1900`.
+pub fn host_for(addr: &SocketAddr) -> Host {
+    let hostname = match *addr {
+        SocketAddr::V4(n) => n.ip().to_string(),
+        SocketAddr::V6(n) if n.scope_id() != 0 => format!("[{}%{}]", n.ip(), n.scope_id()),
+        SocketAddr::V6(n) => format!("[{}]", n.ip()),
+    };
+
+    Host {
+        hostname,
+        port: Some(addr.port()),
+    }
+}
 
 /// Trait for viewing the contents of a header structure.
 pub trait HeaderRef: Debug {
@@ -75,6 +105,10 @@ impl HeaderRef for Headers {
     fn get<H>(&self) -> Option<&H>
         where H: Header + HeaderFormat
     {
+        // Headers that were set as a different type, such as `MaxAge` after
+        // `CacheControl`, are only parsed again once their raw value exists.
+        Headers::get_raw(self, H::header_name())?;
+
         Headers::get::<H>(self)
     }
 
@@ -177,3 +211,32 @@ impl HeaderMut for Headers {
 // }
 // }
 // }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{SocketAddr, SocketAddrV6};
+
+    use hyper::header::Host;
+
+    #[test]
+    fn positive_host_for_v4() {
+        let addr: SocketAddr = "239.255.255.250:1900".parse().unwrap();
+
+        assert_eq!(super::host_for(&addr),
+                   Host { hostname: "239.255.255.250".to_owned(), port: Some(1900) });
+    }
+
+    #[test]
+    fn positive_host_for_v6() {
+        let addr: SocketAddr = "[ff02::c]:1900".parse().unwrap();
+
+        assert_eq!(super::host_for(&addr).to_string(), "[ff02::c]:1900");
+    }
+
+    #[test]
+    fn positive_host_for_scoped_v6() {
+        let addr = SocketAddr::V6(SocketAddrV6::new("ff02::c".parse().unwrap(), 1900, 0, 3));
+
+        assert_eq!(super::host_for(&addr).to_string(), "[ff02::c%3]:1900");
+    }
+}
@@ -1,4 +1,7 @@
+use std::collections::hash_map::RandomState;
 use std::fmt::{Formatter, Result};
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
 
 use hyper::error::{self, Error};
 use hyper::header::{HeaderFormat, Header};
@@ -13,6 +16,11 @@ pub const MX_HEADER_MIN: u8 = 1;
 /// Maximum wait time specified in the `UPnP` 1.0 standard.
 pub const MX_HEADER_MAX: u8 = 120;
 
+/// Largest wait time control points should use, per the `UPnP` 1.1 standard.
+///
+/// Larger values are valid but let responses pile up, so they are best avoided.
+pub const MX_HEADER_RECOMMENDED_MAX: u8 = 5;
+
 /// Represents a header used to specify the maximum time that devices should wait
 /// before sending a response.
 ///
@@ -32,6 +40,24 @@ impl MX {
             Ok(MX(wait_bound))
         }
     }
+
+    /// Get a random delay between zero and the wait bound, for a device to
+    /// wait before responding to a search.
+    ///
+    /// Spreading responses over the wait bound keeps many devices from all
+    /// answering at once.
+    pub fn response_delay(&self) -> Duration {
+        let max_millis = u64::from(self.0) * 1000;
+
+        Duration::from_millis(random_u64() % (max_millis + 1))
+    }
+}
+
+/// Get a random number, seeded from the per process hash keys.
+///
+/// This is not suitable for anything but spreading out timings.
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
 }
 
 impl Header for MX {
@@ -0,0 +1,77 @@
+use std::fmt::{Formatter, Result};
+
+use hyper::error::{self, Error};
+use hyper::header::{HeaderFormat, Header};
+
+const NEXTBOOTID_HEADER_NAME: &'static str = "NEXTBOOTID.UPNP.ORG";
+
+/// Represents a header used to announce the boot instance a root device will
+/// use after an `ssdp:update` notification.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct NextBootID(pub u32);
+
+impl Header for NextBootID {
+    fn header_name() -> &'static str {
+        NEXTBOOTID_HEADER_NAME
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> error::Result<Self> {
+        if raw.len() != 1 {
+            return Err(Error::Header);
+        }
+
+        let cow_str = String::from_utf8_lossy(&raw[0][..]);
+
+        // Value needs to be a 31 bit non-negative integer
+        match cow_str.parse::<u32>() {
+            Ok(n) if n <= i32::MAX as u32 => Ok(NextBootID(n)),
+            _ => Err(Error::Header),
+        }
+    }
+}
+
+impl HeaderFormat for NextBootID {
+    fn fmt_header(&self, fmt: &mut Formatter) -> Result {
+        try!(fmt.write_fmt(format_args!("{}", self.0)));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::header::Header;
+
+    use super::NextBootID;
+
+    #[test]
+    fn positive_nextbootid() {
+        let nextbootid_header_value = &[b"1216907401"[..].to_vec()];
+
+        assert_eq!(NextBootID::parse_header(nextbootid_header_value).unwrap(), NextBootID(1216907401));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_overflow() {
+        let nextbootid_header_value = &[b"2290649224"[..].to_vec()];
+
+        NextBootID::parse_header(nextbootid_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_nan() {
+        let nextbootid_header_value = &[b"2290wow649224"[..].to_vec()];
+
+        NextBootID::parse_header(nextbootid_header_value).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_negative() {
+        let nextbootid_header_value = &[b"-1"[..].to_vec()];
+
+        NextBootID::parse_header(nextbootid_header_value).unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+use std::env;
+use std::fmt::{self, Display, Formatter};
+
+use hyper::header::{Server, UserAgent};
+
+use {SSDPErrorKind, SSDPResult};
+
+/// UPnP Version Advertised By Default
+const DEFAULT_UPNP_VERSION: &str = "1.1";
+
+/// Builds the `OS/version UPnP/version product/version` value of the `SERVER`
+/// and `USER-AGENT` headers.
+///
+/// The operating system is detected when not set explicitly.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ServerInfo {
+    os: String,
+    upnp: String,
+    product: String,
+}
+
+impl ServerInfo {
+    /// Construct a new ServerInfo for the given product token, such as `MyApp/1.0`.
+    pub fn new(product_token: &str) -> ServerInfo {
+        ServerInfo {
+            os: format!("{}/{}", env::consts::OS, os_version()),
+            upnp: format!("UPnP/{}", DEFAULT_UPNP_VERSION),
+            product: product_token.to_owned(),
+        }
+    }
+
+    /// Set the operating system name and version instead of detecting them.
+    pub fn os(mut self, name: &str, version: &str) -> ServerInfo {
+        self.os = format!("{}/{}", name, version);
+        self
+    }
+
+    /// Set the UPnP version the device or control point implements.
+    pub fn upnp_version(mut self, version: &str) -> ServerInfo {
+        self.upnp = format!("UPnP/{}", version);
+        self
+    }
+
+    /// Set the product name and version.
+    pub fn product(mut self, name: &str, version: &str) -> ServerInfo {
+        self.product = format!("{}/{}", name, version);
+        self
+    }
+
+    /// Parse the value of a received `SERVER` or `USER-AGENT` header.
+    ///
+    /// The UPnP version token separates the operating system from the product,
+    /// so products containing spaces and the comma separated form used by
+    /// UPnP 1.0 devices are accepted. Returns `None` if any token is missing.
+    pub fn parse(value: &str) -> Option<ServerInfo> {
+        let tokens: Vec<&str> = value.split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+            .collect();
+        let upnp_index = tokens.iter().position(|token| token.to_ascii_lowercase().starts_with("upnp/"))?;
+
+        if upnp_index == 0 || upnp_index + 1 == tokens.len() {
+            return None;
+        }
+
+        Some(ServerInfo {
+            os: tokens[..upnp_index].join(" "),
+            upnp: tokens[upnp_index].to_owned(),
+            product: tokens[upnp_index + 1..].join(" "),
+        })
+    }
+
+    /// Get the operating system token, such as `Linux/4.9`.
+    pub fn os_token(&self) -> &str {
+        &self.os
+    }
+
+    /// Get the UPnP version token, such as `UPnP/1.1`.
+    pub fn upnp_token(&self) -> &str {
+        &self.upnp
+    }
+
+    /// Get the product token, such as `MyApp/1.0`.
+    pub fn product_token(&self) -> &str {
+        &self.product
+    }
+
+    /// Build a `SERVER` header, as sent by devices.
+    pub fn to_server(&self) -> SSDPResult<Server> {
+        self.to_value().map(Server)
+    }
+
+    /// Build a `USER-AGENT` header, as sent by control points.
+    pub fn to_user_agent(&self) -> SSDPResult<UserAgent> {
+        self.to_value().map(UserAgent)
+    }
+
+    /// Get the header value, checking that no token contains a line break.
+    pub fn to_value(&self) -> SSDPResult<String> {
+        let has_line_break = |token: &String| token.contains('\r') || token.contains('\n');
+
+        if [&self.os, &self.upnp, &self.product].iter().any(|token| has_line_break(token)) {
+            Err(SSDPErrorKind::InvalidHeader("SERVER", "Product Token Contains A Line Break").into())
+        } else {
+            Ok(self.to_string())
+        }
+    }
+}
+
+impl Display for ServerInfo {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.os, self.upnp, self.product)
+    }
+}
+
+/// Get the version of the running operating system, where it can be found.
+#[cfg(target_os = "linux")]
+fn os_version() -> String {
+    use std::fs;
+
+    fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|release| release.trim().to_owned())
+        .ok()
+        .filter(|release| !release.is_empty() && !release.contains(char::is_whitespace))
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+/// Get the version of the running operating system, where it can be found.
+#[cfg(not(target_os = "linux"))]
+fn os_version() -> String {
+    "unknown".to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use hyper::header::{Server, UserAgent};
+
+    use super::ServerInfo;
+
+    #[test]
+    fn positive_explicit_tokens() {
+        let info = ServerInfo::new("ignored/0.1")
+            .os("Linux", "4.9")
+            .upnp_version("2.0")
+            .product("Player", "1.0");
+
+        assert_eq!(info.to_value().unwrap(), "Linux/4.9 UPnP/2.0 Player/1.0");
+    }
+
+    #[test]
+    fn positive_detected_os() {
+        let value = ServerInfo::new("MyApp/1.0").to_value().unwrap();
+        let tokens: Vec<&str> = value.split(' ').collect();
+
+        assert_eq!(tokens.len(), 3);
+        assert!(tokens[0].starts_with(&format!("{}/", env::consts::OS)));
+        assert_eq!(&tokens[1..], &["UPnP/1.1", "MyApp/1.0"]);
+    }
+
+    #[test]
+    fn positive_parse_round_trip() {
+        let info = ServerInfo::parse("Linux/4.9 UPnP/2.0 Player/1.0").unwrap();
+
+        assert_eq!(info.os_token(), "Linux/4.9");
+        assert_eq!(info.upnp_token(), "UPnP/2.0");
+        assert_eq!(info.product_token(), "Player/1.0");
+        assert_eq!(info.to_value().unwrap(), "Linux/4.9 UPnP/2.0 Player/1.0");
+    }
+
+    #[test]
+    fn positive_parse_comma_separated() {
+        let info = ServerInfo::parse("Linux/3.14, UPnP/1.0, Portable SDK for UPnP devices/1.6.19").unwrap();
+
+        assert_eq!(info.os_token(), "Linux/3.14");
+        assert_eq!(info.upnp_token(), "UPnP/1.0");
+        assert_eq!(info.product_token(), "Portable SDK for UPnP devices/1.6.19");
+    }
+
+    #[test]
+    fn positive_headers() {
+        let info = ServerInfo::new("MyApp/1.0").os("Linux", "4.9");
+
+        assert_eq!(info.to_server().unwrap(), Server("Linux/4.9 UPnP/1.1 MyApp/1.0".to_owned()));
+        assert_eq!(info.to_user_agent().unwrap(), UserAgent("Linux/4.9 UPnP/1.1 MyApp/1.0".to_owned()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_parse_missing_product() {
+        ServerInfo::parse("Linux/4.9 UPnP/1.1").unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_parse_missing_upnp() {
+        ServerInfo::parse("MiniUPnPd/1.4").unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_line_break() {
+        ServerInfo::new("MyApp/1.0").os("Linux\r\nX-Injected: true", "4.9").to_value().unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt::{Display, Error, Formatter};
+use std::result::Result;
+
+use FieldMap;
+use header::{NT, ST};
+
+const ST_ALL_VALUE: &'static str = "ssdp:all";
+
+const ROOT_DEVICE_VALUE: &'static str = "rootdevice";
+
+/// Category of the target named by an `ST` or `NT` header.
+///
+/// Formatting a target yields the value it was parsed from.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum TargetType {
+    /// Every device and service, `ssdp:all`.
+    All,
+    /// Root devices only, `upnp:rootdevice`.
+    RootDevice,
+    /// A single device, with the value of `uuid:<value>`.
+    Uuid(String),
+    /// A device or service type, with the value of `urn:<value>`.
+    Urn(String),
+    /// Any other target.
+    Other(FieldMap),
+}
+
+impl TargetType {
+    /// Parse a target from a raw header value.
+    ///
+    /// Returns `None` if the value is neither `ssdp:all` nor a valid `FieldMap`.
+    pub fn parse_bytes(value: &[u8]) -> Option<TargetType> {
+        if value == ST_ALL_VALUE.as_bytes() {
+            Some(TargetType::All)
+        } else {
+            FieldMap::parse_bytes(value).map(TargetType::from)
+        }
+    }
+}
+
+impl From<FieldMap> for TargetType {
+    fn from(field: FieldMap) -> TargetType {
+        match field {
+            FieldMap::UPnP(ref n) if n == ROOT_DEVICE_VALUE => TargetType::RootDevice,
+            FieldMap::UUID(n) => TargetType::Uuid(n),
+            FieldMap::URN(n) => TargetType::Urn(n),
+            n => TargetType::Other(n),
+        }
+    }
+}
+
+impl<'a> From<&'a ST> for TargetType {
+    fn from(st: &'a ST) -> TargetType {
+        match *st {
+            ST::All => TargetType::All,
+            ST::Target(ref n) => TargetType::from(n.clone()),
+        }
+    }
+}
+
+impl<'a> From<&'a NT> for TargetType {
+    fn from(nt: &'a NT) -> TargetType {
+        TargetType::from(nt.0.clone())
+    }
+}
+
+impl Display for TargetType {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            TargetType::All => f.write_str(ST_ALL_VALUE),
+            TargetType::RootDevice => Display::fmt(&FieldMap::upnp(ROOT_DEVICE_VALUE), f),
+            TargetType::Uuid(ref n) => Display::fmt(&FieldMap::uuid(&n[..]), f),
+            TargetType::Urn(ref n) => Display::fmt(&FieldMap::urn(&n[..]), f),
+            TargetType::Other(ref n) => Display::fmt(n, f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use FieldMap;
+    use super::TargetType;
+
+    fn round_trip(value: &str) -> TargetType {
+        let target = TargetType::parse_bytes(value.as_bytes()).unwrap();
+        assert_eq!(target.to_string(), value);
+
+        target
+    }
+
+    #[test]
+    fn positive_all() {
+        assert_eq!(round_trip("ssdp:all"), TargetType::All);
+    }
+
+    #[test]
+    fn positive_root_device() {
+        assert_eq!(round_trip("upnp:rootdevice"), TargetType::RootDevice);
+    }
+
+    #[test]
+    fn positive_uuid() {
+        assert_eq!(round_trip("uuid:a984bc8c-aaf0-5dff-b980-00d098bda247"),
+                   TargetType::Uuid("a984bc8c-aaf0-5dff-b980-00d098bda247".to_owned()));
+    }
+
+    #[test]
+    fn positive_urn() {
+        assert_eq!(round_trip("urn:schemas-upnp-org:device:MediaServer:1"),
+                   TargetType::Urn("schemas-upnp-org:device:MediaServer:1".to_owned()));
+    }
+
+    #[test]
+    fn positive_other() {
+        assert_eq!(round_trip("upnp:other"), TargetType::Other(FieldMap::upnp("other")));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_no_colon() {
+        TargetType::parse_bytes(b"rootdevice").unwrap();
+    }
+}
@@ -21,6 +21,30 @@ impl USN {
     pub fn new(field: FieldMap, opt_field: Option<FieldMap>) -> USN {
         USN(field, opt_field)
     }
+
+    /// Get the device uuid, without its `uuid:` prefix.
+    ///
+    /// Returns `None` if the first field is not a uuid, which devices are
+    /// required to send but not all of them do.
+    pub fn uuid(&self) -> Option<&str> {
+        match self.0 {
+            FieldMap::UUID(ref n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Get the device or service type following the `::` separator.
+    ///
+    /// Returns `None` for the bare uuid advertisement of a device.
+    pub fn target(&self) -> Option<&FieldMap> {
+        self.1.as_ref()
+    }
+}
+
+impl Display for USN {
+    fn fmt(&self, fmt: &mut Formatter) -> Result {
+        self.fmt_header(fmt)
+    }
 }
 
 impl Header for USN {
@@ -103,7 +127,7 @@ mod tests {
     use hyper::header::Header;
 
     use super::USN;
-    use FieldMap::{UPnP, UUID, URN, Unknown};
+    use FieldMap::{self, UPnP, UUID, URN, Unknown};
 
     #[test]
     fn positive_double_pair() {
@@ -163,6 +187,33 @@ mod tests {
         assert!(second.is_none());
     }
 
+    #[test]
+    fn positive_uuid_and_target() {
+        let header = &["uuid:device-UUID::urn:schemas-upnp-org:service:AVTransport:1".to_string().into_bytes()];
+        let usn = USN::parse_header(header).unwrap();
+
+        assert_eq!(usn.uuid(), Some("device-UUID"));
+        assert_eq!(usn.target(), Some(&FieldMap::urn("schemas-upnp-org:service:AVTransport:1")));
+        assert_eq!(usn.to_string(), "uuid:device-UUID::urn:schemas-upnp-org:service:AVTransport:1");
+    }
+
+    #[test]
+    fn positive_bare_uuid() {
+        let usn = USN::parse_header(&["uuid:device-UUID".to_string().into_bytes()]).unwrap();
+
+        assert_eq!(usn.uuid(), Some("device-UUID"));
+        assert_eq!(usn.target(), None);
+        assert_eq!(usn.to_string(), "uuid:device-UUID");
+    }
+
+    #[test]
+    fn positive_unknown_shape_preserved() {
+        let usn = USN::parse_header(&["vendor:1234::upnp:rootdevice".to_string().into_bytes()]).unwrap();
+
+        assert_eq!(usn.uuid(), None);
+        assert_eq!(usn.to_string(), "vendor:1234::upnp:rootdevice");
+    }
+
     #[test]
     #[should_panic]
     fn negative_empty() {
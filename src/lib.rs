@@ -13,9 +13,10 @@ extern crate hyper;
 extern crate log;
 extern crate get_if_addrs;
 extern crate socket2;
-extern crate time;
 #[macro_use]
 extern crate error_chain;
+#[cfg(target_os = "linux")]
+extern crate libc;
 
 mod error;
 mod field;
@@ -25,7 +26,8 @@ mod receiver;
 pub mod header;
 pub mod message;
 
-pub use error::{SSDPError, SSDPErrorKind, SSDPResult, SSDPResultExt};
+pub use error::{ParseComponent, SSDPError, SSDPErrorKind, SSDPResult, SSDPResultExt};
 pub use field::FieldMap;
 pub use net::IpVersionMode;
-pub use receiver::{SSDPIter, SSDPReceiver};
+pub use net::packet::PacketInfo;
+pub use receiver::{SharedIter, SharedReceiver, Sink, SSDPFilter, SSDPIter, SSDPReceiver, StopHandle};
@@ -0,0 +1,383 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use FieldMap;
+use error::SSDPResult;
+use header::{HeaderRef, Location, NT, USN};
+use message::Config;
+use message::multicast::Multicast;
+use message::notify::NotifyMessage;
+
+/// State shared between an `Advertiser` and its background thread.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum State {
+    Running,
+    Paused,
+    Stopped,
+}
+
+type Shared = Arc<(Mutex<State>, Condvar)>;
+
+/// Function sending a single notify message.
+type Sender = Arc<dyn Fn(&NotifyMessage) -> SSDPResult<()> + Send + Sync>;
+
+/// Periodically multicasts a set of notify messages from a background thread.
+///
+/// Advertisements stop when the `Advertiser` is dropped.
+pub struct Advertiser {
+    messages: Vec<NotifyMessage>,
+    send: Sender,
+    // Held for a whole round of sends, so a byebye never goes out mid round
+    send_lock: Arc<Mutex<()>>,
+    shared: Shared,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Advertiser {
+    /// Start multicasting the given messages every `interval`.
+    ///
+    /// The UDA recommends an interval of less than half the max-age that the
+    /// messages advertise.
+    pub fn start(messages: Vec<NotifyMessage>, interval: Duration, config: Config) -> Advertiser {
+        Advertiser::with_sender(messages, interval, move |message: &NotifyMessage| {
+            message.multicast_with_config(&config)
+        })
+    }
+
+    /// Start advertising the messages using the given function to send each one.
+    fn with_sender<F>(messages: Vec<NotifyMessage>, interval: Duration, send: F) -> Advertiser
+    where
+        F: Fn(&NotifyMessage) -> SSDPResult<()> + Send + Sync + 'static,
+    {
+        let send: Sender = Arc::new(send);
+        let send_lock = Arc::new(Mutex::new(()));
+        let shared: Shared = Arc::new((Mutex::new(State::Running), Condvar::new()));
+
+        let thread_messages = messages.clone();
+        let thread_send = send.clone();
+        let thread_send_lock = send_lock.clone();
+        let thread_shared = shared.clone();
+        let handle = thread::spawn(move || {
+            advertise(&thread_messages, interval, &thread_shared, &thread_send_lock, &*thread_send)
+        });
+
+        Advertiser {
+            messages,
+            send,
+            send_lock,
+            shared,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop sending advertisements until `resume()` is called.
+    ///
+    /// If `byebye` is set, an `ssdp:byebye` message is multicast for every
+    /// advertised message so that control points forget about them right away.
+    /// A round of advertisements already being sent is finished first, so no
+    /// `ssdp:alive` follows the byebye.
+    pub fn pause(&self, byebye: bool) -> SSDPResult<()> {
+        self.set_state(State::Paused);
+        let _round = self.send_lock.lock().unwrap();
+
+        if byebye {
+            for message in &self.messages {
+                if let (Some(nt), Some(usn)) = (message.get::<NT>(), message.get::<USN>()) {
+                    (self.send)(&NotifyMessage::byebye(nt.clone(), usn.clone()))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resume sending advertisements, starting with an immediate one.
+    pub fn resume(&self) {
+        self.set_state(State::Running);
+    }
+
+    /// Check if advertisements are currently paused.
+    pub fn is_paused(&self) -> bool {
+        *self.shared.0.lock().unwrap() == State::Paused
+    }
+
+    fn set_state(&self, state: State) {
+        let (ref lock, ref cvar) = *self.shared;
+
+        *lock.lock().unwrap() = state;
+        cvar.notify_all();
+    }
+}
+
+impl Drop for Advertiser {
+    fn drop(&mut self) {
+        self.set_state(State::Stopped);
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// A UPnP device along with its services and embedded devices.
+///
+/// Types are given without the `urn:` prefix, such as
+/// `schemas-upnp-org:device:MediaServer:1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceTree {
+    uuid: String,
+    device_type: String,
+    services: Vec<String>,
+    devices: Vec<DeviceTree>,
+}
+
+impl DeviceTree {
+    /// Construct a new DeviceTree for the device with the given uuid and type.
+    pub fn new(uuid: &str, device_type: &str) -> DeviceTree {
+        DeviceTree {
+            uuid: uuid.to_owned(),
+            device_type: device_type.to_owned(),
+            services: Vec::new(),
+            devices: Vec::new(),
+        }
+    }
+
+    /// Add a service type offered by this device.
+    pub fn service(mut self, service_type: &str) -> DeviceTree {
+        if !self.services.iter().any(|service| service == service_type) {
+            self.services.push(service_type.to_owned());
+        }
+        self
+    }
+
+    /// Add a device embedded in this device.
+    pub fn device(mut self, device: DeviceTree) -> DeviceTree {
+        self.devices.push(device);
+        self
+    }
+
+    /// Build the `ssdp:alive` messages required to advertise this device as a
+    /// root device.
+    ///
+    /// The root device is announced with `upnp:rootdevice`, its uuid and its
+    /// type. Embedded devices are announced with their uuid and type, and every
+    /// device announces each of its service types once.
+    pub fn advertisements(&self, location: &Location, max_age: u32) -> Vec<NotifyMessage> {
+        let mut targets = vec![(self.uuid.clone(), FieldMap::upnp("rootdevice"))];
+        self.collect_targets(&mut targets);
+
+        targets.into_iter()
+            .map(|(uuid, target)| {
+                let usn = match target {
+                    FieldMap::UUID(_) => USN(FieldMap::uuid(&uuid[..]), None),
+                    ref n => USN(FieldMap::uuid(&uuid[..]), Some(n.clone())),
+                };
+
+                NotifyMessage::alive(NT(target), usn, location.clone(), max_age)
+            })
+            .collect()
+    }
+
+    /// Push the uuid, device type and service types of this device and all of
+    /// its embedded devices.
+    fn collect_targets(&self, targets: &mut Vec<(String, FieldMap)>) {
+        targets.push((self.uuid.clone(), FieldMap::uuid(&self.uuid[..])));
+        targets.push((self.uuid.clone(), FieldMap::urn(&self.device_type[..])));
+
+        for service in &self.services {
+            targets.push((self.uuid.clone(), FieldMap::urn(&service[..])));
+        }
+
+        for device in &self.devices {
+            device.collect_targets(targets);
+        }
+    }
+}
+
+/// Send the messages every interval for as long as the shared state is running.
+///
+/// Each round of sends holds the send lock.
+///
+/// This should almost always be run in it's own thread.
+fn advertise(messages: &[NotifyMessage],
+             interval: Duration,
+             shared: &Shared,
+             send_lock: &Mutex<()>,
+             send: &dyn Fn(&NotifyMessage) -> SSDPResult<()>) {
+    let (ref lock, ref cvar) = **shared;
+
+    loop {
+        {
+            let mut state = lock.lock().unwrap();
+            while *state == State::Paused {
+                state = cvar.wait(state).unwrap();
+            }
+
+            if *state == State::Stopped {
+                return;
+            }
+        }
+
+        {
+            // Skip the round if paused while waiting, it must not follow a byebye
+            let _round = send_lock.lock().unwrap();
+            if *lock.lock().unwrap() != State::Running {
+                continue;
+            }
+
+            for message in messages {
+                if let Err(err) = send(message) {
+                    warn!("Failed to send advertisement: {}", err);
+                }
+            }
+        }
+
+        let deadline = Instant::now() + interval;
+        let mut state = lock.lock().unwrap();
+        while *state == State::Running {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+
+            state = cvar.wait_timeout(state, deadline - now).unwrap().0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    use header::{HeaderRef, Location, NT, NTS, USN};
+    use message::notify::NotifyMessage;
+    use super::{Advertiser, DeviceTree};
+
+    fn counting_advertiser(interval: Duration) -> (Advertiser, Arc<AtomicUsize>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let thread_count = count.clone();
+
+        let messages = vec![NotifyMessage::new()];
+        let advertiser = Advertiser::with_sender(messages, interval, move |_| {
+            thread_count.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        });
+
+        (advertiser, count)
+    }
+
+    #[test]
+    fn positive_readvertises() {
+        let (_advertiser, count) = counting_advertiser(Duration::from_millis(10));
+
+        thread::sleep(Duration::from_millis(200));
+        assert!(count.load(Ordering::SeqCst) > 1);
+    }
+
+    #[test]
+    fn positive_pause_stops_advertising() {
+        let (advertiser, count) = counting_advertiser(Duration::from_millis(10));
+
+        advertiser.pause(false).unwrap();
+        assert!(advertiser.is_paused());
+
+        thread::sleep(Duration::from_millis(50));
+        let paused_count = count.load(Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(count.load(Ordering::SeqCst), paused_count);
+    }
+
+    #[test]
+    fn positive_resume_advertises_immediately() {
+        let (advertiser, count) = counting_advertiser(Duration::from_secs(3600));
+
+        advertiser.pause(false).unwrap();
+        thread::sleep(Duration::from_millis(50));
+        let paused_count = count.load(Ordering::SeqCst);
+
+        advertiser.resume();
+        thread::sleep(Duration::from_millis(100));
+
+        assert!(!advertiser.is_paused());
+        assert_eq!(count.load(Ordering::SeqCst), paused_count + 1);
+    }
+
+    #[test]
+    fn positive_byebye_after_round() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let thread_sent = sent.clone();
+
+        let location = Location("http://192.168.1.2/root.xml".to_owned());
+        let messages = DeviceTree::new("root", "schemas-upnp-org:device:Basic:1")
+            .advertisements(&location, 1800);
+        let advertiser = Advertiser::with_sender(messages, Duration::from_millis(10), move |message| {
+            thread::sleep(Duration::from_millis(20));
+            thread_sent.lock().unwrap().push(message.subtype().unwrap());
+            Ok(())
+        });
+
+        // Pause partway through the first round
+        thread::sleep(Duration::from_millis(30));
+        advertiser.pause(true).unwrap();
+        thread::sleep(Duration::from_millis(100));
+
+        let sent = sent.lock().unwrap();
+        let first_byebye = sent.iter().position(|nts| *nts == NTS::ByeBye).unwrap();
+
+        assert_eq!(&sent[..first_byebye], &[NTS::Alive, NTS::Alive, NTS::Alive]);
+        assert!(sent[first_byebye..].iter().all(|nts| *nts == NTS::ByeBye));
+    }
+
+    #[test]
+    fn positive_device_tree_advertisements() {
+        let tree = DeviceTree::new("root", "schemas-upnp-org:device:MediaServer:1")
+            .service("schemas-upnp-org:service:ContentDirectory:1")
+            .service("schemas-upnp-org:service:ConnectionManager:1")
+            .device(DeviceTree::new("embedded", "schemas-upnp-org:device:Printer:1")
+                .service("schemas-upnp-org:service:ConnectionManager:1"));
+        let location = Location("http://192.168.1.2/root.xml".to_owned());
+
+        let pairs: Vec<(String, String)> = tree.advertisements(&location, 1800)
+            .iter()
+            .map(|message| {
+                let NT(ref nt) = *message.get::<NT>().unwrap();
+                let usn = message.get::<USN>().unwrap().to_string();
+
+                (nt.to_string(), usn)
+            })
+            .collect();
+
+        let expected = vec![("upnp:rootdevice", "uuid:root::upnp:rootdevice"),
+                            ("uuid:root", "uuid:root"),
+                            ("urn:schemas-upnp-org:device:MediaServer:1",
+                             "uuid:root::urn:schemas-upnp-org:device:MediaServer:1"),
+                            ("urn:schemas-upnp-org:service:ContentDirectory:1",
+                             "uuid:root::urn:schemas-upnp-org:service:ContentDirectory:1"),
+                            ("urn:schemas-upnp-org:service:ConnectionManager:1",
+                             "uuid:root::urn:schemas-upnp-org:service:ConnectionManager:1"),
+                            ("uuid:embedded", "uuid:embedded"),
+                            ("urn:schemas-upnp-org:device:Printer:1",
+                             "uuid:embedded::urn:schemas-upnp-org:device:Printer:1"),
+                            ("urn:schemas-upnp-org:service:ConnectionManager:1",
+                             "uuid:embedded::urn:schemas-upnp-org:service:ConnectionManager:1")];
+        let expected: Vec<(String, String)> =
+            expected.into_iter().map(|(nt, usn)| (nt.to_owned(), usn.to_owned())).collect();
+
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn positive_device_tree_duplicate_service() {
+        let tree = DeviceTree::new("root", "schemas-upnp-org:device:Basic:1")
+            .service("schemas-upnp-org:service:Dimming:1")
+            .service("schemas-upnp-org:service:Dimming:1");
+        let location = Location("http://192.168.1.2/root.xml".to_owned());
+
+        assert_eq!(tree.advertisements(&location, 1800).len(), 4);
+    }
+}
@@ -0,0 +1,214 @@
+use std::net::SocketAddr;
+
+use FieldMap;
+use header::{HeaderRef, Server, ServerInfo, ST, USN};
+use message::search::SearchResponse;
+
+/// A device on the network along with every search response it sent.
+///
+/// Responses are grouped by the uuid found in their `USN` header.
+#[derive(Debug, Clone)]
+pub struct DiscoveredDevice {
+    uuid: String,
+    responses: Vec<(SearchResponse, SocketAddr)>,
+}
+
+impl DiscoveredDevice {
+    /// Group the given responses by device, preserving the order in which
+    /// devices were first seen.
+    ///
+    /// Responses without a uuid in their `USN` header are discarded.
+    pub fn group<I>(responses: I) -> Vec<DiscoveredDevice>
+        where I: IntoIterator<Item = (SearchResponse, SocketAddr)>
+    {
+        let mut devices: Vec<DiscoveredDevice> = Vec::new();
+
+        for (response, src) in responses {
+            let uuid = match response_uuid(&response) {
+                Some(n) => n,
+                None => {
+                    debug!("Discarding response from {} without a device uuid", src);
+                    continue;
+                }
+            };
+
+            match devices.iter().position(|device| device.uuid == uuid) {
+                Some(index) => devices[index].responses.push((response, src)),
+                None => {
+                    devices.push(DiscoveredDevice {
+                        uuid,
+                        responses: vec![(response, src)],
+                    })
+                }
+            }
+        }
+
+        devices
+    }
+
+    /// Get the uuid of this device.
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    /// Get the responses sent by this device, in the order they were received.
+    pub fn responses(&self) -> &[(SearchResponse, SocketAddr)] {
+        &self.responses
+    }
+
+    /// Get the device and service type urns this device advertised, without
+    /// duplicates.
+    ///
+    /// The `upnp:rootdevice` and bare uuid advertisements are not types and are
+    /// excluded.
+    pub fn service_types(&self) -> Vec<FieldMap> {
+        let mut types = Vec::new();
+
+        for (response, _) in &self.responses {
+            let urn = match (response.get::<ST>(), response.get::<USN>()) {
+                (Some(&ST::Target(ref n @ FieldMap::URN(_))), _) => n,
+                (_, Some(&USN(_, Some(ref n @ FieldMap::URN(_))))) => n,
+                _ => continue,
+            };
+
+            if !types.contains(urn) {
+                types.push(urn.clone());
+            }
+        }
+
+        types
+    }
+
+    /// Get the `SERVER` header of the first response that had one.
+    pub fn server(&self) -> Option<&str> {
+        self.responses.iter().find_map(|(response, _)| response.get::<Server>().map(|Server(n)| &n[..]))
+    }
+
+    /// Get the product token from the `SERVER` header, such as `Sonos/52.1`.
+    ///
+    /// The header is parsed as with `ServerInfo::parse`, so everything after
+    /// the UPnP version token is the product.
+    pub fn server_product(&self) -> Option<String> {
+        self.server().and_then(ServerInfo::parse).map(|info| info.product_token().to_owned())
+    }
+
+    /// Check if the `SERVER` header of this device mentions the given vendor,
+    /// ignoring case.
+    pub fn matches_vendor(&self, vendor: &str) -> bool {
+        match self.server() {
+            Some(server) => server.to_ascii_lowercase().contains(&vendor.to_ascii_lowercase()),
+            None => false,
+        }
+    }
+}
+
+/// Keep only the devices whose `SERVER` header mentions the given vendor.
+///
+/// Matching is a case insensitive substring match against the whole header,
+/// so both `Sonos` and `sonos/1.0` work. Devices without a `SERVER` header are
+/// excluded.
+pub fn by_vendor<'a, I>(devices: I, vendor: &'a str) -> impl Iterator<Item = DiscoveredDevice> + 'a
+    where I: IntoIterator<Item = DiscoveredDevice>,
+          I::IntoIter: 'a
+{
+    devices.into_iter().filter(move |device| device.matches_vendor(vendor))
+}
+
+/// Get the device uuid out of the `USN` header of the given response.
+fn response_uuid(response: &SearchResponse) -> Option<String> {
+    response.get::<USN>().and_then(USN::uuid).map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use FieldMap;
+    use header::{HeaderMut, Server, ST, USN};
+    use message::search::SearchResponse;
+    use super::DiscoveredDevice;
+
+    fn device_with_server(uuid: &str, server: Option<&str>) -> DiscoveredDevice {
+        let (mut response, src) = response(uuid, FieldMap::upnp("rootdevice"));
+        if let Some(n) = server {
+            response.set(Server(n.to_owned()));
+        }
+
+        DiscoveredDevice::group(vec![(response, src)]).remove(0)
+    }
+
+    fn response(uuid: &str, target: FieldMap) -> (SearchResponse, SocketAddr) {
+        let mut response = SearchResponse::new();
+
+        response.set(ST::Target(target.clone()));
+        match target {
+            FieldMap::UUID(_) => response.set(USN(FieldMap::uuid(uuid), None)),
+            n => response.set(USN(FieldMap::uuid(uuid), Some(n))),
+        }
+
+        (response, "192.168.1.2:1900".parse().unwrap())
+    }
+
+    #[test]
+    fn positive_group_by_uuid() {
+        let devices = DiscoveredDevice::group(vec![response("first", FieldMap::upnp("rootdevice")),
+                                                   response("second", FieldMap::upnp("rootdevice")),
+                                                   response("first", FieldMap::uuid("first"))]);
+
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].uuid(), "first");
+        assert_eq!(devices[0].responses().len(), 2);
+        assert_eq!(devices[1].uuid(), "second");
+    }
+
+    #[test]
+    fn positive_service_types() {
+        let media_server = FieldMap::urn("schemas-upnp-org:device:MediaServer:1");
+        let directory = FieldMap::urn("schemas-upnp-org:service:ContentDirectory:1");
+
+        let devices = DiscoveredDevice::group(vec![response("device", FieldMap::upnp("rootdevice")),
+                                                   response("device", FieldMap::uuid("device")),
+                                                   response("device", media_server.clone()),
+                                                   response("device", directory.clone()),
+                                                   response("device", media_server.clone())]);
+
+        assert_eq!(devices[0].service_types(), vec![media_server, directory]);
+    }
+
+    #[test]
+    fn positive_server_product() {
+        let device = device_with_server("device", Some("Linux/3.14 UPnP/1.0 Sonos/52.1"));
+
+        assert_eq!(device.server_product(), Some("Sonos/52.1".to_owned()));
+    }
+
+    #[test]
+    fn positive_server_product_odd_header() {
+        let device = device_with_server("device", Some("Roku UPnP/1.0 MiniUPnPd/1.4"));
+        assert_eq!(device.server_product(), Some("MiniUPnPd/1.4".to_owned()));
+
+        let server = "Linux/3.14, UPnP/1.0, Portable SDK for UPnP devices/1.6";
+        let device = device_with_server("device", Some(server));
+        assert_eq!(device.server_product(), Some("Portable SDK for UPnP devices/1.6".to_owned()));
+
+        let device = device_with_server("device", Some("UPnP/1.0"));
+        assert_eq!(device.server_product(), None);
+    }
+
+    #[test]
+    fn positive_by_vendor() {
+        let devices = vec![device_with_server("sonos", Some("Linux UPnP/1.0 Sonos/52.1")),
+                           device_with_server("roku", Some("Roku/9.4 UPnP/1.0 Roku/9.4")),
+                           device_with_server("silent", None)];
+
+        let sonos: Vec<_> = super::by_vendor(devices, "SONOS").collect();
+
+        assert_eq!(sonos.len(), 1);
+        assert_eq!(sonos[0].uuid(), "sonos");
+    }
+
+    #[test]
+    fn negative_matches_vendor_without_server() {
+        assert!(!device_with_server("silent", None).matches_vendor(""));
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+/// Default number of replies a single source may receive per window.
+pub const DEFAULT_MAX_REPLIES: u32 = 10;
+
+/// Default window over which replies to a single source are counted.
+pub const DEFAULT_REPLY_WINDOW_SECS: u64 = 1;
+
+/// Number of tracked sources after which expired entries are pruned.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Limits the number of replies sent to any single source address.
+///
+/// A device answering search requests can be abused to flood a third party by
+/// spoofing the source of those requests, so responders should consult a
+/// `RateLimiter` before replying to each request they receive.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    max_replies: u32,
+    window: Duration,
+    sources: HashMap<IpAddr, (Instant, u32)>,
+    dropped: u64,
+}
+
+impl RateLimiter {
+    /// Construct a new RateLimiter allowing `max_replies` replies to each
+    /// source ip address per `window`.
+    pub fn new(max_replies: u32, window: Duration) -> RateLimiter {
+        RateLimiter {
+            max_replies,
+            window,
+            sources: HashMap::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Returns true if a reply to the given source is allowed.
+    ///
+    /// Replies that are not allowed are counted as dropped.
+    pub fn check(&mut self, src: &SocketAddr) -> bool {
+        self.check_at(src, Instant::now())
+    }
+
+    /// Number of replies that have been dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    fn check_at(&mut self, src: &SocketAddr, now: Instant) -> bool {
+        if self.sources.len() >= PRUNE_THRESHOLD {
+            let window = self.window;
+            self.sources.retain(|_, &mut (start, _)| now.duration_since(start) < window);
+        }
+
+        let entry = self.sources.entry(src.ip()).or_insert((now, 0));
+        if now.duration_since(entry.0) >= self.window {
+            *entry = (now, 0);
+        }
+
+        if entry.1 < self.max_replies {
+            entry.1 += 1;
+            true
+        } else {
+            self.dropped += 1;
+            debug!("Rate limiting reply to {}", src);
+            false
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter::new(DEFAULT_MAX_REPLIES, Duration::from_secs(DEFAULT_REPLY_WINDOW_SECS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::time::{Duration, Instant};
+
+    use super::RateLimiter;
+
+    #[test]
+    fn positive_caps_single_source() {
+        let mut limiter = RateLimiter::new(5, Duration::from_secs(1));
+        let src: SocketAddr = "192.168.1.2:1900".parse().unwrap();
+        let now = Instant::now();
+
+        let allowed = (0..100).filter(|_| limiter.check_at(&src, now)).count();
+
+        assert_eq!(allowed, 5);
+        assert_eq!(limiter.dropped(), 95);
+    }
+
+    #[test]
+    fn positive_sources_limited_separately() {
+        let mut limiter = RateLimiter::new(1, Duration::from_secs(1));
+        let now = Instant::now();
+
+        assert!(limiter.check_at(&"192.168.1.2:1900".parse().unwrap(), now));
+        assert!(limiter.check_at(&"192.168.1.3:1900".parse().unwrap(), now));
+    }
+
+    #[test]
+    fn positive_window_resets() {
+        let mut limiter = RateLimiter::new(1, Duration::from_secs(1));
+        let src: SocketAddr = "192.168.1.2:1900".parse().unwrap();
+        let now = Instant::now();
+
+        assert!(limiter.check_at(&src, now));
+        assert!(!limiter.check_at(&src, now));
+        assert!(limiter.check_at(&src, now + Duration::from_secs(1)));
+    }
+}
@@ -1,7 +1,9 @@
-use std::net::{SocketAddr, IpAddr};
+use std::io;
+use std::net::{SocketAddr, IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket};
 
 use error::SSDPResult;
 use message::{self, Config};
+use message::multicast;
 use receiver::{SSDPReceiver, FromRawSSDP};
 use net;
 
@@ -12,6 +14,9 @@ pub trait Listen {
     /// Listen for messages on all local network interfaces.
     ///
     /// This will call `listen_with_config()` with _default_ values.
+    ///
+    /// The receiver listens until it is dropped or stopped through its
+    /// `SSDPReceiver::stop_handle()`.
     fn listen() -> SSDPResult<SSDPReceiver<Self::Message>> {
         Self::listen_with_config(&Default::default())
     }
@@ -25,67 +30,157 @@ pub trait Listen {
     /// you will have to stop listening and start listening again,
     /// or we recommend using `listen_anyaddr_with_config()` instead.
     fn listen_with_config(config: &Config) -> SSDPResult<SSDPReceiver<Self::Message>> {
-        let mut ipv4_sock = None;
-        let mut ipv6_sock = None;
+        let sockets = bind_multicast(config)?;
+
+        Ok(try!(SSDPReceiver::new(sockets, None)))
+    }
+
+    /// Listen on any interface
+    ///
+    /// # Important
+    ///
+    /// This version of the `listen`()` will _bind_ to `INADDR_ANY` instead of binding to each interface
+    ///
+    /// Only the IP versions allowed by the `IpVersionMode` of the config are
+    /// listened on.
+    fn listen_anyaddr_with_config(config: &Config) -> SSDPResult<SSDPReceiver<Self::Message>> {
+        let mut sockets = Vec::new();
+        let (ipv4_mcast, ipv6_mcast) = allowed_groups(config)?;
 
-        // Generate a list of reused sockets on the standard multicast address.
-        let addrs: Vec<SocketAddr> = try!(message::map_local(|&addr| Ok(Some(addr))));
+        // Ipv4
+        if let Some(mcast_ip) = ipv4_mcast {
+            let bind = || net::bind_reuse(("0.0.0.0", config.port));
+            let ipv4_sock = try!(net::retry_bind(config.bind_retries, bind));
+            try!(ipv4_sock.join_multicast_v4(&mcast_ip, &Ipv4Addr::UNSPECIFIED));
+            sockets.push(ipv4_sock);
+        }
 
-        for addr in addrs {
-            match addr {
-                SocketAddr::V4(_) => {
-                    let mcast_ip = config.ipv4_addr.parse().unwrap();
+        // Ipv6
+        if let Some(mcast_ip) = ipv6_mcast {
+            let bind = || net::bind_reuse(("::", config.port));
+            let ipv6_sock = try!(net::retry_bind(config.bind_retries, bind));
+            try!(ipv6_sock.join_multicast_v6(&mcast_ip, 0));
+            sockets.push(ipv6_sock);
+        }
 
-                    if ipv4_sock.is_none() {
-                        ipv4_sock = Some(try!(net::bind_reuse(("0.0.0.0", config.port))));
-                    }
+        Ok(try!(SSDPReceiver::new(sockets, None)))
+    }
+}
 
-                    let ref sock = ipv4_sock.as_ref().unwrap();
+/// Parse the configured multicast groups of the IP versions allowed by the
+/// `IpVersionMode` of the config.
+///
+/// Groups of other IP versions are never used, so they are not parsed either.
+fn allowed_groups(config: &Config) -> io::Result<(Option<Ipv4Addr>, Option<Ipv6Addr>)> {
+    let ipv4_mcast = if config.mode.allows(&"0.0.0.0:0".parse().unwrap()) {
+        Some(config.ipv4_addr.parse().map_err(multicast::invalid_group)?)
+    } else {
+        None
+    };
+    let ipv6_mcast = if config.mode.allows(&"[::]:0".parse().unwrap()) {
+        Some(config.ipv6_addr.parse().map_err(multicast::invalid_group)?)
+    } else {
+        None
+    };
+
+    Ok((ipv4_mcast, ipv6_mcast))
+}
 
-                    debug!("Joining ipv4 multicast {} at iface: {}", mcast_ip, addr);
-                    try!(net::join_multicast(&sock, &addr, &mcast_ip));
+/// Bind reused sockets on the configured port and join the configured multicast
+/// groups on every local interface allowed by the `IpVersionMode` of the config.
+pub(crate) fn bind_multicast(config: &Config) -> SSDPResult<Vec<UdpSocket>> {
+    let mut ipv4_sock = None;
+    let mut ipv6_sock = None;
+
+    let (ipv4_mcast, ipv6_mcast) = allowed_groups(config)?;
+
+    // Generate a list of reused sockets on the standard multicast address.
+    let allowed = |addr: &SocketAddr| Ok(Some(*addr).filter(|n| config.mode.allows(n)));
+    let addrs: Vec<SocketAddr> = try!(message::map_selected(config.receive_global_ipv6,
+                                                            &config.interfaces,
+                                                            allowed));
+
+    for addr in addrs {
+        match (addr, ipv4_mcast, ipv6_mcast) {
+            (SocketAddr::V4(_), Some(mcast_ip), _) => {
+                if ipv4_sock.is_none() {
+                    let bind = || net::bind_reuse(("0.0.0.0", config.port));
+                    ipv4_sock = Some(try!(net::retry_bind(config.bind_retries, bind)));
                 }
-                SocketAddr::V6(_) => {
-                    let mcast_ip = config.ipv6_addr.parse().unwrap();
-
-                    if ipv6_sock.is_none() {
-                        ipv6_sock = Some(try!(net::bind_reuse(("::", config.port))));
-                    }
 
-                    let ref sock = ipv6_sock.as_ref().unwrap();
+                let ref sock = ipv4_sock.as_ref().unwrap();
 
-                    debug!("Joining ipv6 multicast {} at iface: {}", mcast_ip, addr);
-                    try!(net::join_multicast(&sock, &addr, &IpAddr::V6(mcast_ip)));
+                debug!("Joining ipv4 multicast {} at iface: {}", mcast_ip, addr);
+                try!(net::join_multicast(&sock, &addr, &IpAddr::V4(mcast_ip)));
+            }
+            (SocketAddr::V6(_), _, Some(mcast_ip)) => {
+                if ipv6_sock.is_none() {
+                    let bind = || net::bind_reuse(("::", config.port));
+                    ipv6_sock = Some(try!(net::retry_bind(config.bind_retries, bind)));
                 }
+
+                let ref sock = ipv6_sock.as_ref().unwrap();
+
+                debug!("Joining ipv6 multicast {} at iface: {}", mcast_ip, addr);
+                try!(net::join_multicast(&sock, &addr, &IpAddr::V6(mcast_ip)));
             }
+            _ => (),
         }
+    }
 
-        let sockets = vec![ipv4_sock, ipv6_sock]
-            .into_iter()
-            .flat_map(|opt_interface| opt_interface)
-            .collect();
+    let sockets = vec![ipv4_sock, ipv6_sock]
+        .into_iter()
+        .flat_map(|opt_interface| opt_interface)
+        .collect();
 
-        Ok(try!(SSDPReceiver::new(sockets, None)))
+    Ok(sockets)
+}
+
+#[cfg(test)]
+mod tests {
+    use message::{Config, Listen, NotifyListener};
+    use net::IpVersionMode;
+
+    #[test]
+    fn positive_bind_multicast_mode() {
+        let config = Config::new().set_port(0).set_mode(IpVersionMode::V4Only);
+        let sockets = super::bind_multicast(&config).unwrap();
+
+        assert!(sockets.iter().all(|sock| sock.local_addr().unwrap().is_ipv4()));
     }
 
-    /// Listen on any interface
-    ///
-    /// # Important
-    ///
-    /// This version of the `listen`()` will _bind_ to `INADDR_ANY` instead of binding to each interface
-    #[cfg(linux)]
-    fn listen_anyaddr_with_config(config: &Config) -> SSDPResult<SSDPReceiver<Self::Message>> {
-        // Ipv4
-        let mcast_ip = config.ipv4_address.parse().unwrap();
-        let ipv4_sock = try!(net::bind_reuse(("0.0.0.0", config.port)));
-        try!(ipv4_sock.join_multicast_v4(&mcast_ip, &"0.0.0.0".parse().unwrap()));
+    #[test]
+    fn positive_bind_multicast_unused_group() {
+        let config = Config::new()
+            .set_port(0)
+            .set_mode(IpVersionMode::V4Only)
+            .set_ipv6_addr("not an address");
 
-        // Ipv6
-        let mcast_ip = config.ipv6_address.parse().unwrap();
-        let ipv6_sock = try!(net::bind_reuse(("::", config.port)));
-        try!(ipv6_sock.join_multicast_v6(&mcast_ip, 0));
+        super::bind_multicast(&config).unwrap();
+    }
 
-        let sockets = vec![ipv4_sock, ipv6_sock];
-        Ok(try!(SSDPReceiver::new(sockets, None)))
+    #[test]
+    fn positive_anyaddr_mode() {
+        let config = Config::new()
+            .set_port(0)
+            .set_mode(IpVersionMode::V4Only)
+            .set_ipv6_addr("not an address");
+
+        NotifyListener::listen_anyaddr_with_config(&config).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_anyaddr_invalid_group() {
+        let config = Config::new().set_port(0).set_ipv4_addr("not an address");
+
+        NotifyListener::listen_anyaddr_with_config(&config).unwrap();
+    }
+
+    #[test]
+    fn negative_listen_invalid_group() {
+        let config = Config::new().set_port(0).set_ipv6_addr("not an address");
+
+        assert!(NotifyListener::listen_with_config(&config).is_err());
     }
 }
@@ -0,0 +1,135 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+
+use header::Location;
+use net::packet::PacketInfo;
+
+/// Computes the `LOCATION` to reply with for a search request.
+///
+/// Multi-homed devices are reachable at a different address on each network,
+/// so responders should ask a `LocationProvider` for the location of every
+/// request instead of replying with a single static URL.
+pub trait LocationProvider {
+    /// Get the location to advertise in reply to a request described by `info`.
+    ///
+    /// Returning `None` means no reply should be sent.
+    fn location(&self, info: &PacketInfo) -> Option<Location>;
+}
+
+impl<F> LocationProvider for F
+    where F: Fn(&PacketInfo) -> Option<Location>
+{
+    fn location(&self, info: &PacketInfo) -> Option<Location> {
+        self(info)
+    }
+}
+
+/// Points the location at the local address a request was received on.
+///
+/// When the receiving socket is bound to a wildcard address, the local
+/// address the host would use to reach the requester is used instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceLocation {
+    port: u16,
+    path: String,
+}
+
+impl InterfaceLocation {
+    /// Construct a new InterfaceLocation serving the description at the given
+    /// port and path.
+    pub fn new(port: u16, path: &str) -> InterfaceLocation {
+        let path = if path.starts_with('/') {
+            path.to_owned()
+        } else {
+            format!("/{}", path)
+        };
+
+        InterfaceLocation { port, path }
+    }
+}
+
+impl LocationProvider for InterfaceLocation {
+    fn location(&self, info: &PacketInfo) -> Option<Location> {
+        let local_ip = if info.local_addr.ip().is_unspecified() {
+            match route_local_ip(&info.src_addr) {
+                Some(n) => n,
+                None => {
+                    debug!("No local address found to reach {}", info.src_addr);
+                    return None;
+                }
+            }
+        } else {
+            info.local_addr.ip()
+        };
+
+        Some(Location(format!("http://{}{}", SocketAddr::new(local_ip, self.port), self.path)))
+    }
+}
+
+/// Get the local address used to send packets to the given remote address.
+fn route_local_ip(remote: &SocketAddr) -> Option<IpAddr> {
+    let unspecified = match *remote {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+
+    // Connecting a udp socket sends nothing, it only selects the route.
+    let sock = UdpSocket::bind((unspecified, 0)).ok()?;
+    sock.connect(remote).ok()?;
+
+    sock.local_addr().ok().map(|addr| addr.ip()).filter(|ip| !ip.is_unspecified())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::time::Instant;
+
+    use header::Location;
+    use net::packet::PacketInfo;
+    use super::{InterfaceLocation, LocationProvider};
+
+    fn info(src_addr: &str, local_addr: &str) -> PacketInfo {
+        PacketInfo {
+            src_addr: src_addr.parse().unwrap(),
+            local_addr: local_addr.parse().unwrap(),
+            if_index: None,
+            dst_addr: None,
+            arrived_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn positive_bound_interface() {
+        let provider = InterfaceLocation::new(8080, "description.xml");
+        let location = provider.location(&info("192.168.1.20:1900", "192.168.1.2:1900"));
+
+        assert_eq!(location, Some(Location("http://192.168.1.2:8080/description.xml".to_owned())));
+    }
+
+    #[test]
+    fn positive_bound_interface_ipv6() {
+        let provider = InterfaceLocation::new(8080, "/description.xml");
+        let location = provider.location(&info("[fe80::2]:1900", "[fe80::1]:1900"));
+
+        assert_eq!(location, Some(Location("http://[fe80::1]:8080/description.xml".to_owned())));
+    }
+
+    #[test]
+    fn positive_wildcard_interface() {
+        let provider = InterfaceLocation::new(8080, "/description.xml");
+        let location = provider.location(&info("127.0.0.1:1900", "0.0.0.0:1900"));
+
+        assert_eq!(location, Some(Location("http://127.0.0.1:8080/description.xml".to_owned())));
+    }
+
+    #[test]
+    fn positive_closure_provider() {
+        let provider = |info: &PacketInfo| match info.src_addr {
+            SocketAddr::V4(_) => Some(Location("http://10.0.0.1/root.xml".to_owned())),
+            SocketAddr::V6(_) => None,
+        };
+
+        assert!(provider.location(&info("[fe80::2]:1900", "[fe80::1]:1900")).is_none());
+        assert!(provider.location(&info("127.0.0.1:1900", "127.0.0.1:1900")).is_some());
+    }
+}
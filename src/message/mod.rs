@@ -1,32 +1,59 @@
 //! Messaging primitives for discovering devices and services.
 
+use std::collections::{HashMap, HashSet};
 use std::io;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::time::Duration;
 
+use net::{self, IpVersionMode};
 use net::connector::UdpConnector;
-use net::IpVersionMode;
+use header::ServerInfo;
+use PacketInfo;
 
+mod advertise;
+mod device;
+mod limit;
+mod location;
 pub mod listen;
 pub mod multicast;
 mod notify;
 mod search;
+mod session;
 mod ssdp;
 
 use get_if_addrs;
 
+pub use message::advertise::{Advertiser, DeviceTree};
+pub use message::device::{by_vendor, DiscoveredDevice};
+pub use message::limit::{RateLimiter, DEFAULT_MAX_REPLIES, DEFAULT_REPLY_WINDOW_SECS};
+pub use message::location::{InterfaceLocation, LocationProvider};
 pub use message::listen::Listen;
-pub use message::multicast::Multicast;
+pub use message::multicast::{can_multicast, Backoff, Multicast, SendSchedule};
 pub use message::notify::{NotifyListener, NotifyMessage};
-pub use message::search::{SearchListener, SearchRequest, SearchResponse};
+pub use message::search::{SearchListener, SearchRequest, SearchResponse, UniqueResponses};
+pub use message::session::{Advertisement, DiscoveryEvent, DiscoverySession};
+pub use message::ssdp::classify;
 
 /// Multicast Socket Information
 pub const UPNP_MULTICAST_IPV4_ADDR: &'static str = "239.255.255.250";
 pub const UPNP_MULTICAST_IPV6_LINK_LOCAL_ADDR: &'static str = "FF02::C";
+pub const UPNP_MULTICAST_IPV6_SITE_LOCAL_ADDR: &'static str = "FF05::C";
+pub const UPNP_MULTICAST_IPV6_ORG_LOCAL_ADDR: &'static str = "FF08::C";
 pub const UPNP_MULTICAST_PORT: u16 = 1900;
 
 /// Default TTL For Multicast
 pub const UPNP_MULTICAST_TTL: u32 = 2;
 
+/// Largest Outgoing Message That Safely Fits In A Single Datagram
+///
+/// This leaves room for the IPv6 and UDP headers within a 1500 byte MTU. Each
+/// advertisement should be sent as its own message, so hitting this limit
+/// usually means too much was packed into one message.
+pub const MAX_SSDP_MESSAGE_SIZE: usize = 1452;
+
+/// Product Token Used When None Is Configured
+pub const DEFAULT_PRODUCT_TOKEN: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
 /// Enumerates different types of SSDP messages.
 #[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
 pub enum MessageType {
@@ -38,13 +65,109 @@ pub enum MessageType {
     Response,
 }
 
+/// Scope of the IPv6 multicast group that messages are sent to and received on.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Ipv6Scope {
+    /// Group `FF02::C`, limited to a single link.
+    LinkLocal,
+    /// Group `FF05::C`, spanning the routed links of a site.
+    SiteLocal,
+    /// Group `FF08::C`, spanning the sites of an organization.
+    OrganizationLocal,
+}
+
+impl Ipv6Scope {
+    /// Get the UPnP multicast group of this scope.
+    pub fn group_addr(&self) -> &'static str {
+        match *self {
+            Ipv6Scope::LinkLocal => UPNP_MULTICAST_IPV6_LINK_LOCAL_ADDR,
+            Ipv6Scope::SiteLocal => UPNP_MULTICAST_IPV6_SITE_LOCAL_ADDR,
+            Ipv6Scope::OrganizationLocal => UPNP_MULTICAST_IPV6_ORG_LOCAL_ADDR,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Config {
     pub ipv4_addr: String,
     pub ipv6_addr: String,
     pub port: u16,
     pub ttl: u32,
+    pub interface_ttl: InterfaceTtl,
     pub mode: IpVersionMode,
+    pub strict: bool,
+    pub check_size: bool,
+    pub match_target: bool,
+    pub same_subnet: bool,
+    pub bind_retries: u32,
+    pub product_token: Option<String>,
+    pub server_info: Option<ServerInfo>,
+    pub send_global_ipv6: bool,
+    pub receive_global_ipv6: bool,
+    /// Replaces the `search_repeat` count of earlier versions, which is now
+    /// set through `set_search_repeat`.
+    pub search_schedule: SendSchedule,
+    pub receive_timeout: Option<Duration>,
+    pub multicast_loop: Option<bool>,
+    pub interfaces: InterfaceSet,
+}
+
+/// Multicast TTL overrides for individual interfaces, keyed by local address.
+///
+/// Interfaces without an override use the TTL of the `Config`.
+#[derive(Clone, Debug, Default)]
+pub struct InterfaceTtl {
+    ttls: HashMap<IpAddr, u32>,
+}
+
+impl InterfaceTtl {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Use the given TTL for the interface with the given local address.
+    pub fn set<A: Into<IpAddr>>(mut self, local_addr: A, ttl: u32) -> Self {
+        self.ttls.insert(local_addr.into(), ttl);
+        self
+    }
+
+    /// Get the TTL for the interface with the given local address, if overridden.
+    pub fn get(&self, local_addr: &IpAddr) -> Option<u32> {
+        self.ttls.get(local_addr).cloned()
+    }
+}
+
+/// Local interfaces to send and listen on, selected by name or address.
+///
+/// An empty set selects every interface.
+#[derive(Clone, Debug, Default)]
+pub struct InterfaceSet {
+    names: HashSet<String>,
+    addrs: HashSet<IpAddr>,
+}
+
+impl InterfaceSet {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Select the interface with the given name, such as `eth0`.
+    pub fn add_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.names.insert(name.into());
+        self
+    }
+
+    /// Select the interface with the given local address.
+    pub fn add_addr<A: Into<IpAddr>>(mut self, local_addr: A) -> Self {
+        self.addrs.insert(local_addr.into());
+        self
+    }
+
+    /// Check if the interface with the given name and local address is selected.
+    pub fn contains(&self, name: &str, local_addr: &IpAddr) -> bool {
+        (self.names.is_empty() && self.addrs.is_empty()) || self.names.contains(name) ||
+        self.addrs.contains(local_addr)
+    }
 }
 
 trait IpProperties {
@@ -69,13 +192,21 @@ impl IpProperties for std::net::Ipv4Addr {
 
 impl IpProperties for std::net::Ipv6Addr {
     fn is_global_addr(&self) -> bool {
+        let segments = self.segments();
+
+        // Link-local fe80::/10 and deprecated site-local fec0::/10
+        let is_link_or_site_local = (segments[0] & 0xffc0) == 0xfe80 || (segments[0] & 0xffc0) == 0xfec0;
+        // Unique local fc00::/7
+        let is_unique_local = (segments[0] & 0xfe00) == 0xfc00;
+        // Documentation 2001:db8::/32
+        let is_documentation = segments[0] == 0x2001 && segments[1] == 0xdb8;
+
         !self.is_multicast()
             && !self.is_loopback()
-            && !(self.segments()[0] & 0xffc0) == 0xfe80
-            && !(self.segments()[0] & 0xffc0) == 0xfec0
-            && !(self.segments()[0] & 0xfe00) == 0xfc00
             && !self.is_unspecified()
-            && !((self.segments()[0] == 0x2001) && (self.segments()[1] == 0xdb8))
+            && !is_link_or_site_local
+            && !is_unique_local
+            && !is_documentation
     }
 }
 
@@ -94,20 +225,181 @@ impl Config {
         self
     }
 
+    /// Use the UPnP multicast group of the given scope as the IPv6 address.
+    ///
+    /// Only link-local groups are sent with the scope id of the interface,
+    /// wider groups are routed and sent out of the interface of each connector.
+    pub fn set_ipv6_scope(self, value: Ipv6Scope) -> Self {
+        self.set_ipv6_addr(value.group_addr())
+    }
+
     pub fn set_port(mut self, value: u16) -> Self {
         self.port = value;
         self
     }
 
+    /// Set the multicast TTL used on all interfaces.
+    ///
+    /// For IPv4 this is the time to live of the datagram, for IPv6 it is the
+    /// hop limit. Both are decremented per router, but values above 1 are
+    /// only meaningful on networks that route multicast traffic.
     pub fn set_ttl(mut self, value: u32) -> Self {
         self.ttl = value;
         self
     }
 
+    /// Set how many times binding a socket is retried while its address is
+    /// still in use, as can happen right after a restart.
+    pub fn set_bind_retries(mut self, value: u32) -> Self {
+        self.bind_retries = value;
+        self
+    }
+
+    /// Set multicast TTL overrides for individual interfaces.
+    pub fn set_interface_ttl(mut self, value: InterfaceTtl) -> Self {
+        self.interface_ttl = value;
+        self
+    }
+
+    /// Only send and listen on the given interfaces.
+    ///
+    /// Together with `set_interface_ttl` this controls where discovery traffic
+    /// goes on hosts with many interfaces.
+    pub fn set_interfaces(mut self, value: InterfaceSet) -> Self {
+        self.interfaces = value;
+        self
+    }
+
+    /// Get the multicast TTL to use on the interface with the given local address.
+    pub fn ttl_for(&self, local_addr: &IpAddr) -> u32 {
+        self.interface_ttl.get(local_addr).unwrap_or(self.ttl)
+    }
+
+    /// Set the product token, such as `MyApp/1.0`, to identify this application
+    /// with when a message is sent without a `USER-AGENT` or `SERVER` header.
+    ///
+    /// Tokens containing a line break are rejected when a message is sent.
+    pub fn set_product_token(mut self, value: &str) -> Self {
+        self.product_token = Some(value.to_owned());
+        self
+    }
+
+    /// Get the configured product token, or `DEFAULT_PRODUCT_TOKEN`.
+    pub fn product_token(&self) -> &str {
+        self.product_token.as_ref().map_or(DEFAULT_PRODUCT_TOKEN, |token| &token[..])
+    }
+
+    /// Set every token of the generated `USER-AGENT` and `SERVER` headers.
+    ///
+    /// This takes precedence over the product token.
+    pub fn set_server_info(mut self, value: ServerInfo) -> Self {
+        self.server_info = Some(value);
+        self
+    }
+
+    /// Get the configured server info, or one built from the product token.
+    pub fn server_info(&self) -> ServerInfo {
+        self.server_info.clone().unwrap_or_else(|| ServerInfo::new(self.product_token()))
+    }
+
+    /// Send from interfaces with a global IPv6 address.
+    ///
+    /// Every non-loopback interface is used by default. Clear this to only send
+    /// from link-local and unique local IPv6 interfaces, so discovery traffic is
+    /// not sent out to the global scope.
+    pub fn set_send_global_ipv6(mut self, value: bool) -> Self {
+        self.send_global_ipv6 = value;
+        self
+    }
+
+    /// Listen on interfaces with a global IPv6 address.
+    ///
+    /// Every non-loopback interface is used by default. Clear this to only listen
+    /// on link-local and unique local IPv6 interfaces.
+    pub fn set_receive_global_ipv6(mut self, value: bool) -> Self {
+        self.receive_global_ipv6 = value;
+        self
+    }
+
+    /// Set how many times a multicast search is sent, to make up for lost
+    /// datagrams. The UPnP specification recommends sending it two or three times.
+    ///
+    /// Repeats are sent after a short fixed delay. Devices answering more than
+    /// one of them are reported each time.
+    pub fn set_search_repeat(mut self, value: u32) -> Self {
+        self.search_schedule = SendSchedule::new(value);
+        self
+    }
+
+    /// Set when a multicast search is sent, such as repeats with a growing
+    /// delay for lossy networks.
+    ///
+    /// Responses are received for the `MX` based timeout after the last send.
+    pub fn set_search_schedule(mut self, value: SendSchedule) -> Self {
+        self.search_schedule = value;
+        self
+    }
+
+    /// Set how long to wait for search responses, instead of deriving it from
+    /// the `MX` header of the request.
+    ///
+    /// The `MX` header is still sent unchanged, this only extends or shortens
+    /// how long responses are listened for. Unlike the `MX` based timeout, which
+    /// restarts with every response, this is a hard deadline counted from the
+    /// first send. Repeats scheduled after it are skipped.
+    pub fn set_receive_timeout(mut self, value: Duration) -> Self {
+        self.receive_timeout = Some(value);
+        self
+    }
+
+    /// Set whether sent multicast is looped back to the local host, so that a
+    /// searcher and a responder on the same machine can see each other.
+    ///
+    /// By default the socket option is left at the system default.
+    pub fn set_multicast_loop(mut self, value: bool) -> Self {
+        self.multicast_loop = Some(value);
+        self
+    }
+
     pub fn set_mode(mut self, value: IpVersionMode) -> Self {
         self.mode = value;
         self
     }
+
+    /// Reject received messages that are valid HTTP but not a valid reply,
+    /// such as search responses with a non-200 status code.
+    ///
+    /// By default such messages are kept so that broken devices are still found.
+    pub fn set_strict(mut self, value: bool) -> Self {
+        self.strict = value;
+        self
+    }
+
+    /// Reject outgoing messages larger than `MAX_SSDP_MESSAGE_SIZE` instead of
+    /// only logging them.
+    pub fn set_check_size(mut self, value: bool) -> Self {
+        self.check_size = value;
+        self
+    }
+
+    /// Drop search responses whose `ST` header names a different target than
+    /// the request, unless the request searched for `ssdp:all`.
+    ///
+    /// Responses without a readable `ST` header are kept.
+    pub fn set_match_target(mut self, value: bool) -> Self {
+        self.match_target = value;
+        self
+    }
+
+    /// Drop search responses sent from outside the subnet of the interface
+    /// they arrived on, such as those leaking in over a VPN or a bridge.
+    ///
+    /// Dropped responses are logged. Responses are kept if the local subnets
+    /// can not be read.
+    pub fn set_same_subnet(mut self, value: bool) -> Self {
+        self.same_subnet = value;
+        self
+    }
 }
 
 impl Default for Config {
@@ -117,63 +409,394 @@ impl Default for Config {
             ipv6_addr: UPNP_MULTICAST_IPV6_LINK_LOCAL_ADDR.to_string(),
             port: UPNP_MULTICAST_PORT,
             ttl: UPNP_MULTICAST_TTL,
+            interface_ttl: InterfaceTtl::new(),
             mode: IpVersionMode::Any,
+            strict: false,
+            check_size: false,
+            match_target: false,
+            same_subnet: false,
+            bind_retries: net::DEFAULT_BIND_RETRIES,
+            product_token: None,
+            server_info: None,
+            send_global_ipv6: true,
+            receive_global_ipv6: true,
+            search_schedule: SendSchedule::default(),
+            receive_timeout: None,
+            multicast_loop: None,
+            interfaces: InterfaceSet::new(),
         }
     }
 }
 
-/// Generate `UdpConnector` objects for all local `IPv4` interfaces.
-fn all_local_connectors(multicast_ttl: Option<u32>, filter: &IpVersionMode) -> io::Result<Vec<UdpConnector>> {
+/// Generate `UdpConnector` objects for all selected local interfaces.
+///
+/// The multicast ttl of each connector is looked up by its local address.
+///
+/// Interfaces with a global IPv6 address are only used if `global_ipv6` is set.
+///
+/// Interfaces whose connector can not be created are logged and skipped.
+fn all_local_connectors<T>(multicast_ttl: T,
+                           filter: &IpVersionMode,
+                           bind_retries: u32,
+                           global_ipv6: bool,
+                           interfaces: &InterfaceSet)
+                           -> io::Result<Vec<UdpConnector>>
+where
+    T: Fn(&IpAddr) -> Option<u32>,
+{
     trace!("Fetching all local connectors");
-    map_local(|&addr| match (filter, addr) {
+    let addrs = try!(map_selected(global_ipv6, interfaces, |&addr| Ok(Some(addr))));
+
+    map_skipping_failures(addrs, |&addr| match (filter, addr) {
         (&IpVersionMode::V4Only, SocketAddr::V4(n)) | (&IpVersionMode::Any, SocketAddr::V4(n)) => {
-            Ok(Some(try!(UdpConnector::new((*n.ip(), 0), multicast_ttl))))
+            let ttl = multicast_ttl(&addr.ip());
+            Ok(Some(UdpConnector::with_bind_retries((*n.ip(), 0), ttl, bind_retries)?))
         }
         (&IpVersionMode::V6Only, SocketAddr::V6(n)) | (&IpVersionMode::Any, SocketAddr::V6(n)) => {
-            Ok(Some(try!(UdpConnector::new(n, multicast_ttl))))
+            let ttl = multicast_ttl(&addr.ip());
+            Ok(Some(UdpConnector::with_bind_retries(n, ttl, bind_retries)?))
         }
         _ => Ok(None),
     })
 }
 
-/// Invoke the closure for every local address found on the system
+/// Invoke the closure for every address, logging and skipping the addresses
+/// it fails on.
 ///
-/// This method filters out _loopback_ and _global_ addresses.
-fn map_local<F, R>(mut f: F) -> io::Result<Vec<R>>
+/// Returns the last error only if the closure failed on every address it was
+/// invoked on, so that a single broken interface does not stop the others.
+fn map_skipping_failures<F, R>(addrs: Vec<SocketAddr>, mut f: F) -> io::Result<Vec<R>>
 where
     F: FnMut(&SocketAddr) -> io::Result<Option<R>>,
 {
-    let addrs_iter = try!(get_local_addrs());
+    let mut obj_list = Vec::with_capacity(addrs.len());
+    let mut last_err = None;
 
-    let mut obj_list = Vec::with_capacity(addrs_iter.len());
-
-    for addr in addrs_iter {
-        trace!("Found {}", addr);
-        match addr {
-            SocketAddr::V4(n) if !n.ip().is_loopback() => {
-                if let Some(x) = try!(f(&addr)) {
-                    obj_list.push(x);
-                }
+    for addr in addrs {
+        match f(&addr) {
+            Ok(Some(x)) => obj_list.push(x),
+            Ok(None) => (),
+            Err(err) => {
+                warn!("Skipping interface {}: {}", addr, err);
+                last_err = Some(err);
             }
-            // Filter all loopback and global IPv6 addresses
-            SocketAddr::V6(n) if !n.ip().is_loopback() && !n.ip().is_global_addr() => {
-                if let Some(x) = try!(f(&addr)) {
-                    obj_list.push(x);
-                }
+        }
+    }
+
+    match last_err {
+        Some(err) if obj_list.is_empty() => Err(err),
+        _ => Ok(obj_list),
+    }
+}
+
+/// Invoke the closure for every local address of the selected interfaces.
+///
+/// This method filters out _loopback_ addresses, and _global_ IPv6 addresses
+/// unless `global_ipv6` is set.
+fn map_selected<F, R>(global_ipv6: bool, interfaces: &InterfaceSet, mut f: F) -> io::Result<Vec<R>>
+where
+    F: FnMut(&SocketAddr) -> io::Result<Option<R>>,
+{
+    let ifaces = try!(get_local_ifaces());
+
+    let mut obj_list = Vec::with_capacity(ifaces.len());
+
+    for (name, addr) in ifaces {
+        trace!("Found {} on {}", addr, name);
+        if is_usable_local(&addr, global_ipv6) && interfaces.contains(&name, &addr.ip()) {
+            if let Some(x) = f(&addr)? {
+                obj_list.push(x);
             }
-            _ => (),
         }
     }
 
     Ok(obj_list)
 }
 
+/// Check if discovery should use the interface with the given local address.
+fn is_usable_local(addr: &SocketAddr, global_ipv6: bool) -> bool {
+    match *addr {
+        SocketAddr::V4(n) => !n.ip().is_loopback(),
+        SocketAddr::V6(n) => !n.ip().is_loopback() && (global_ipv6 || !n.ip().is_global_addr()),
+    }
+}
+
 /// Generate a list of some object R constructed from all local `Ipv4Addr` objects.
 ///
+/// IPv6 addresses carry the scope id of their interface, so that sockets bound
+/// to them send link-local multicast out of that interface.
+///
 /// If any of the `SocketAddr`'s fail to resolve, this function will not return an error.
 fn get_local_addrs() -> io::Result<Vec<SocketAddr>> {
+    Ok(try!(get_local_ifaces()).into_iter().map(|(_, addr)| addr).collect())
+}
+
+/// Generate a list of the names and addresses of all local interfaces.
+///
+/// See `get_local_addrs`.
+fn get_local_ifaces() -> io::Result<Vec<(String, SocketAddr)>> {
     let iface_iter = try!(get_if_addrs::get_if_addrs()).into_iter();
     Ok(iface_iter
-        .filter_map(|iface| Some(SocketAddr::new(iface.addr.ip(), 0)))
+        .map(|iface| {
+            let addr = match iface.addr.ip() {
+                IpAddr::V4(ip) => SocketAddr::V4(SocketAddrV4::new(ip, 0)),
+                IpAddr::V6(ip) => SocketAddr::V6(SocketAddrV6::new(ip, 0, 0, net::scope_id(&iface.name))),
+            };
+
+            (iface.name, addr)
+        })
         .collect())
 }
+
+/// Address and netmask of a local interface.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct LocalSubnet {
+    addr: IpAddr,
+    netmask: IpAddr,
+}
+
+impl LocalSubnet {
+    /// Check if the given address lies within this subnet.
+    fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.addr, self.netmask, *ip) {
+            (IpAddr::V4(addr), IpAddr::V4(mask), IpAddr::V4(ip)) => {
+                let mask = u32::from(mask);
+                u32::from(addr) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(addr), IpAddr::V6(mask), IpAddr::V6(ip)) => {
+                let mask = u128::from(mask);
+                u128::from(addr) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Generate a list of the subnets of all local interfaces.
+fn get_local_subnets() -> io::Result<Vec<LocalSubnet>> {
+    let iface_iter = try!(get_if_addrs::get_if_addrs()).into_iter();
+    Ok(iface_iter
+        .map(|iface| match iface.addr {
+            get_if_addrs::IfAddr::V4(n) => LocalSubnet { addr: IpAddr::V4(n.ip), netmask: IpAddr::V4(n.netmask) },
+            get_if_addrs::IfAddr::V6(n) => LocalSubnet { addr: IpAddr::V6(n.ip), netmask: IpAddr::V6(n.netmask) },
+        })
+        .collect())
+}
+
+/// Check if a packet was sent from within the subnet of the interface it arrived on.
+///
+/// The interface is found by the local address of the receiving socket. Sockets
+/// bound to the unspecified address accept senders on any local subnet.
+fn is_same_subnet(subnets: &[LocalSubnet], info: &PacketInfo) -> bool {
+    let local_ip = info.local_addr.ip();
+    let src_ip = info.src_addr.ip();
+
+    subnets.iter()
+        .filter(|subnet| local_ip.is_unspecified() || subnet.addr == local_ip)
+        .any(|subnet| subnet.contains(&src_ip))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::time::{Duration, Instant};
+
+    use PacketInfo;
+    use net::connector::UdpConnector;
+    use receiver::SSDPReceiver;
+    use super::{Config, InterfaceSet, InterfaceTtl, IpProperties, Ipv6Scope, LocalSubnet, SearchResponse};
+
+    fn usable_addrs(global_ipv6: bool) -> Vec<SocketAddr> {
+        let addrs: Vec<SocketAddr> = ["127.0.0.1:0", "192.168.1.2:0", "[::1]:0", "[fe80::1]:0", "[fd00::2]:0",
+                                      "[2a00:1450::1]:0"]
+            .iter()
+            .map(|addr| addr.parse().unwrap())
+            .collect();
+
+        addrs.into_iter().filter(|addr| super::is_usable_local(addr, global_ipv6)).collect()
+    }
+
+    fn is_global(addr: &str) -> bool {
+        match addr.parse().unwrap() {
+            IpAddr::V4(ip) => ip.is_global_addr(),
+            IpAddr::V6(ip) => ip.is_global_addr(),
+        }
+    }
+
+    #[test]
+    fn positive_global_ipv6_classification() {
+        assert!(is_global("2a00:1450::1"));
+        assert!(!is_global("fe80::1"));
+        assert!(!is_global("febf::1"));
+        assert!(!is_global("fec0::1"));
+        assert!(!is_global("fc00::1"));
+        assert!(!is_global("fd12:3456::1"));
+        assert!(!is_global("2001:db8::1"));
+        assert!(!is_global("ff02::c"));
+        assert!(!is_global("::1"));
+        assert!(!is_global("::"));
+    }
+
+    #[test]
+    fn positive_global_ipv4_classification() {
+        assert!(is_global("8.8.8.8"));
+        assert!(is_global("192.0.0.9"));
+        assert!(!is_global("10.0.0.1"));
+        assert!(!is_global("172.16.0.1"));
+        assert!(!is_global("192.168.1.2"));
+        assert!(!is_global("169.254.1.1"));
+        assert!(!is_global("127.0.0.1"));
+        assert!(!is_global("192.0.2.1"));
+        assert!(!is_global("0.1.2.3"));
+        assert!(!is_global("255.255.255.255"));
+    }
+
+    #[test]
+    fn positive_global_ipv6_excluded_when_cleared() {
+        let expected: Vec<SocketAddr> = vec!["192.168.1.2:0".parse().unwrap(),
+                                             "[fe80::1]:0".parse().unwrap(),
+                                             "[fd00::2]:0".parse().unwrap()];
+
+        assert_eq!(usable_addrs(false), expected);
+    }
+
+    #[test]
+    fn positive_global_ipv6_used_by_default() {
+        let config = Config::new();
+        let expected: Vec<SocketAddr> = vec!["192.168.1.2:0".parse().unwrap(),
+                                             "[fe80::1]:0".parse().unwrap(),
+                                             "[fd00::2]:0".parse().unwrap(),
+                                             "[2a00:1450::1]:0".parse().unwrap()];
+
+        assert_eq!(usable_addrs(config.send_global_ipv6), expected);
+        assert_eq!(usable_addrs(config.receive_global_ipv6), expected);
+    }
+
+    #[test]
+    fn positive_asymmetric_global_ipv6() {
+        let config = Config::new().set_send_global_ipv6(false);
+        let global: SocketAddr = "[2a00:1450::1]:0".parse().unwrap();
+
+        assert!(!usable_addrs(config.send_global_ipv6).contains(&global));
+        assert!(usable_addrs(config.receive_global_ipv6).contains(&global));
+    }
+
+    #[test]
+    fn positive_all_interfaces_by_default() {
+        let lan = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+
+        assert!(Config::new().interfaces.contains("eth0", &lan));
+    }
+
+    #[test]
+    fn positive_interface_selection() {
+        let lan = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+        let guest = IpAddr::V4(Ipv4Addr::new(192, 168, 2, 1));
+        let interfaces = InterfaceSet::new().add_name("eth0").add_addr(guest);
+
+        assert!(interfaces.contains("eth0", &lan));
+        assert!(interfaces.contains("eth1", &guest));
+        assert!(!interfaces.contains("eth1", &lan));
+    }
+
+    #[test]
+    fn positive_ipv6_scope() {
+        assert_eq!(Config::new().ipv6_addr, "FF02::C");
+        assert_eq!(Config::new().set_ipv6_scope(Ipv6Scope::SiteLocal).ipv6_addr, "FF05::C");
+        assert_eq!(Config::new().set_ipv6_scope(Ipv6Scope::OrganizationLocal).ipv6_addr, "FF08::C");
+    }
+
+    #[test]
+    fn positive_interface_ttl_override() {
+        let guest = Ipv4Addr::new(192, 168, 2, 1);
+        let config = Config::new().set_ttl(4).set_interface_ttl(InterfaceTtl::new().set(guest, 1));
+
+        assert_eq!(config.ttl_for(&IpAddr::V4(guest)), 1);
+    }
+
+    #[test]
+    fn positive_interface_ttl_fallback() {
+        let guest = Ipv4Addr::new(192, 168, 2, 1);
+        let lan = Ipv4Addr::new(192, 168, 1, 1);
+        let config = Config::new().set_ttl(4).set_interface_ttl(InterfaceTtl::new().set(guest, 1));
+
+        assert_eq!(config.ttl_for(&IpAddr::V4(lan)), 4);
+    }
+
+    fn packet_info(src_addr: &str, local_addr: &str) -> PacketInfo {
+        PacketInfo {
+            src_addr: src_addr.parse().unwrap(),
+            local_addr: local_addr.parse().unwrap(),
+            if_index: None,
+            dst_addr: None,
+            arrived_at: Instant::now(),
+        }
+    }
+
+    fn lan_subnets() -> Vec<LocalSubnet> {
+        vec![LocalSubnet {
+                 addr: "192.168.1.2".parse().unwrap(),
+                 netmask: "255.255.255.0".parse().unwrap(),
+             },
+             LocalSubnet {
+                 addr: "fe80::2".parse().unwrap(),
+                 netmask: "ffff:ffff:ffff:ffff::".parse().unwrap(),
+             }]
+    }
+
+    #[test]
+    fn positive_same_subnet() {
+        let subnets = lan_subnets();
+
+        assert!(super::is_same_subnet(&subnets, &packet_info("192.168.1.40:1900", "192.168.1.2:5000")));
+        assert!(super::is_same_subnet(&subnets, &packet_info("[fe80::40]:1900", "[fe80::2]:5000")));
+        assert!(super::is_same_subnet(&subnets, &packet_info("192.168.1.40:1900", "0.0.0.0:1900")));
+    }
+
+    #[test]
+    fn negative_other_subnet() {
+        let subnets = lan_subnets();
+
+        assert!(!super::is_same_subnet(&subnets, &packet_info("10.8.0.5:1900", "192.168.1.2:5000")));
+        assert!(!super::is_same_subnet(&subnets, &packet_info("192.168.1.40:1900", "192.168.7.2:5000")));
+        assert!(!super::is_same_subnet(&subnets, &packet_info("[2a00:1450::1]:1900", "[fe80::2]:5000")));
+    }
+
+    /// Create a connector for the address, failing for any address in 192.0.2.0/24.
+    fn connect_unless_unreachable(addr: &SocketAddr) -> io::Result<Option<UdpConnector>> {
+        match addr.ip() {
+            IpAddr::V4(ip) if ip.octets()[..3] == [192, 0, 2] => {
+                Err(io::Error::new(io::ErrorKind::AddrNotAvailable, "Interface Is Down"))
+            }
+            _ => UdpConnector::new(*addr, None).map(Some),
+        }
+    }
+
+    #[test]
+    fn positive_skip_failing_interface() {
+        let addrs = vec!["192.0.2.1:0".parse().unwrap(), "127.0.0.1:0".parse().unwrap()];
+        let connectors = super::map_skipping_failures(addrs, connect_unless_unreachable).unwrap();
+        assert_eq!(connectors.len(), 1);
+
+        let sock = connectors.into_iter().next().unwrap().deconstruct();
+        let recv_addr = sock.local_addr().unwrap();
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![sock], Some(Duration::from_secs(1))).unwrap();
+
+        let send_sock = UdpConnector::new("127.0.0.1:0", None).unwrap().deconstruct();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+        assert_eq!(receiver.recv().unwrap().0.status_code(), 200);
+    }
+
+    #[test]
+    fn negative_all_interfaces_failing() {
+        let addrs = vec!["192.0.2.1:0".parse().unwrap(), "192.0.2.2:0".parse().unwrap()];
+
+        assert!(super::map_skipping_failures(addrs, connect_unless_unreachable).is_err());
+    }
+
+    #[test]
+    fn positive_no_interfaces() {
+        assert!(super::map_skipping_failures(Vec::new(), connect_unless_unreachable).unwrap().is_empty());
+    }
+}
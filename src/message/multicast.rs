@@ -1,43 +1,442 @@
-use std::net::{SocketAddr, SocketAddrV6};
+use std::io;
+use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use error::SSDPResult;
+use error::{SSDPErrorKind, SSDPResult};
+use header::{self, HeaderMut};
+use net;
 use net::connector::UdpConnector;
 use message::{self, Config};
 use message::ssdp::SSDPMessage;
 
+/// Delay Between Repeated Multicast Sends
+const REPEAT_DELAY_MS: u64 = 100;
+
+/// Growth of the delay between repeated multicast sends.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Backoff {
+    /// Every delay is the initial delay.
+    Fixed,
+    /// The delay before the n-th repeat is n times the initial delay.
+    Linear,
+    /// Every delay is twice the one before it.
+    Exponential,
+}
+
+/// Timing of repeated multicast sends.
+///
+/// The first send goes out immediately, every repeat waits for a delay
+/// which grows by the backoff. A count of zero sends once, like a count of one.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SendSchedule {
+    count: u32,
+    delay: Duration,
+    backoff: Backoff,
+}
+
+impl SendSchedule {
+    /// Construct a schedule sending `count` times with a short fixed delay.
+    pub fn new(count: u32) -> SendSchedule {
+        SendSchedule {
+            count,
+            delay: Duration::from_millis(REPEAT_DELAY_MS),
+            backoff: Backoff::Fixed,
+        }
+    }
+
+    /// Set the delay before the first repeat.
+    pub fn set_delay(mut self, value: Duration) -> Self {
+        self.delay = value;
+        self
+    }
+
+    /// Set how the delay grows for the following repeats.
+    pub fn set_backoff(mut self, value: Backoff) -> Self {
+        self.backoff = value;
+        self
+    }
+
+    /// Get the number of sends, which is at least one.
+    pub fn count(&self) -> u32 {
+        self.count.max(1)
+    }
+
+    /// Get the delay before the send of the given round, the first round being zero.
+    pub fn delay_before(&self, round: u32) -> Duration {
+        let factor = match (round, self.backoff) {
+            (0, _) => 0,
+            (_, Backoff::Fixed) => 1,
+            (n, Backoff::Linear) => n,
+            (n, Backoff::Exponential) => 2u32.saturating_pow(n - 1),
+        };
+
+        self.delay.checked_mul(factor).unwrap_or(Duration::MAX)
+    }
+
+    /// Get the time from the first to the last send.
+    pub fn duration(&self) -> Duration {
+        (0..self.count()).fold(Duration::from_secs(0),
+                               |total, round| total.saturating_add(self.delay_before(round)))
+    }
+}
+
+impl Default for SendSchedule {
+    fn default() -> Self {
+        SendSchedule::new(1)
+    }
+}
+
 
 pub trait Multicast {
     type Item;
 
-    fn multicast(&mut self) -> SSDPResult<Self::Item> {
+    /// Send the message to the standard multicast groups on every local interface.
+    fn multicast(&self) -> SSDPResult<Self::Item> {
         self.multicast_with_config(&Default::default())
     }
 
+    /// Send the message to the multicast groups of the config.
+    ///
+    /// Under `IpVersionMode::Any` the IPv4 group is used on IPv4 interfaces
+    /// and the IPv6 group on IPv6 interfaces, so one call covers both stacks.
     fn multicast_with_config(&self, config: &Config) -> SSDPResult<Self::Item>;
 }
 
 pub fn send(message: &SSDPMessage, config: &Config) -> SSDPResult<Vec<UdpConnector>> {
-    let mut connectors = try!(message::all_local_connectors(Some(config.ttl), &config.mode));
+    send_repeated(message, config, &SendSchedule::default(), None)
+}
+
+/// Send the message by the schedule from the same connectors.
+///
+/// Repeats that would go out after the deadline, if any, are skipped.
+pub fn send_repeated(message: &SSDPMessage,
+                     config: &Config,
+                     schedule: &SendSchedule,
+                     deadline: Option<Instant>)
+                     -> SSDPResult<Vec<UdpConnector>> {
+    let prepared = message.prepare(config)?;
+
+    let mut connectors = try!(message::all_local_connectors(|addr| Some(config.ttl_for(addr)),
+                                                           &config.mode,
+                                                           config.bind_retries,
+                                                           config.send_global_ipv6,
+                                                           &config.interfaces));
+    set_multicast_loop(&connectors, config)?;
 
-    for conn in &mut connectors {
-        match try!(conn.local_addr()) {
+    send_rounds(&prepared, &mut connectors, config, schedule, deadline)?;
+
+    Ok(connectors)
+}
+
+/// Send the message by the schedule from the interface with the given local
+/// address only.
+///
+/// Fails if no local interface has the address.
+pub fn send_on(message: &SSDPMessage,
+               config: &Config,
+               local_ip: IpAddr,
+               schedule: &SendSchedule,
+               deadline: Option<Instant>)
+               -> SSDPResult<Vec<UdpConnector>> {
+    let prepared = message.prepare(config)?;
+
+    let local_addr = match message::get_local_addrs()?.into_iter().find(|addr| addr.ip() == local_ip) {
+        Some(addr) => addr,
+        None => return Err(SSDPErrorKind::UnknownInterface(local_ip).into()),
+    };
+
+    let connector = UdpConnector::with_bind_retries(local_addr,
+                                                    Some(config.ttl_for(&local_ip)),
+                                                    config.bind_retries)?;
+    let mut connectors = vec![connector];
+    set_multicast_loop(&connectors, config)?;
+
+    send_rounds(&prepared, &mut connectors, config, schedule, deadline)?;
+
+    Ok(connectors)
+}
+
+/// Apply the multicast loopback setting of the config, if any, to the connectors.
+fn set_multicast_loop(connectors: &[UdpConnector], config: &Config) -> io::Result<()> {
+    if let Some(on) = config.multicast_loop {
+        for conn in connectors {
+            conn.set_multicast_loop(on)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Send the prepared message by the schedule, skipping the repeats that would
+/// go out after the deadline.
+///
+/// Returns the number of times the message was sent.
+fn send_rounds(prepared: &SSDPMessage,
+               connectors: &mut [UdpConnector],
+               config: &Config,
+               schedule: &SendSchedule,
+               deadline: Option<Instant>)
+               -> SSDPResult<u32> {
+    let mut send_at = Instant::now();
+
+    for round in 0..schedule.count() {
+        send_at = match send_at.checked_add(schedule.delay_before(round)) {
+            Some(n) if round == 0 || deadline.is_none_or(|deadline| n <= deadline) => n,
+            _ => {
+                debug!("Skipping {} sends scheduled past the deadline", schedule.count() - round);
+                return Ok(round);
+            }
+        };
+
+        let now = Instant::now();
+        if send_at > now {
+            thread::sleep(send_at - now);
+        }
+
+        send_to_groups(prepared, connectors, config)?;
+    }
+
+    Ok(schedule.count())
+}
+
+/// Send the prepared message to the multicast group of each connector.
+///
+/// The `HOST` header is set to the group of each connector, so it always
+/// matches the IP version the message is sent with.
+fn send_to_groups(prepared: &SSDPMessage,
+                  connectors: &mut [UdpConnector],
+                  config: &Config)
+                  -> SSDPResult<()> {
+    for conn in connectors {
+        let mcast_addr = match try!(conn.local_addr()) {
             SocketAddr::V4(n) => {
-                let mcast_addr = (config.ipv4_addr.as_str(), config.port);
-                debug!("Sending ipv4 multicast through {} to {:?}", n, mcast_addr);
-                try!(message.send(conn, &mcast_addr));
+                let mcast_addr = try!(net::addr_from_trait((config.ipv4_addr.as_str(), config.port)));
+                debug!("Sending ipv4 multicast through {} to {}", n, mcast_addr);
+                mcast_addr
             }
             SocketAddr::V6(n) => {
                 debug!("Sending Ipv6 multicast through {} to {}:{}", n, config.ipv6_addr, config.port);
-                //try!(message.send(conn, &mcast_addr));
-                try!(message.send(conn,
-                                  &SocketAddrV6::new(try!(FromStr::from_str(config.ipv6_addr.as_str())),
-                                                     config.port,
-                                                     n.flowinfo(),
-                                                     n.scope_id())))
+                let group = try!(FromStr::from_str(config.ipv6_addr.as_str()));
+                SocketAddr::V6(net::ipv6_group_addr(group, config.port, &n))
+            }
+        };
+
+        let mut message = prepared.clone();
+        message.set(header::host_for(&mcast_addr));
+        try!(message.send(conn, mcast_addr));
+    }
+
+    Ok(())
+}
+
+/// Check whether multicast sends are accepted on this host at all.
+///
+/// An empty datagram is sent to the multicast group of each interface allowed
+/// by the config, which SSDP listeners ignore. Returns true if the local send
+/// call succeeded for at least one of them. This does not show that the
+/// datagram reached the network, only that the host did not reject it, which
+/// tells a host without multicast capable interfaces apart from one where no
+/// devices answered.
+pub fn can_multicast(config: &Config) -> io::Result<bool> {
+    let ipv4_mcast = config.ipv4_addr.parse().map_err(invalid_group)?;
+    let ipv6_mcast = config.ipv6_addr.parse().map_err(invalid_group)?;
+    let connectors = message::all_local_connectors(|addr| Some(config.ttl_for(addr)),
+                                                   &config.mode,
+                                                   config.bind_retries,
+                                                   config.send_global_ipv6,
+                                                   &config.interfaces)?;
+    set_multicast_loop(&connectors, config)?;
+
+    let mut success = false;
+    for conn in connectors {
+        let local_addr = conn.local_addr()?;
+        let mcast_addr = match local_addr {
+            SocketAddr::V4(_) => SocketAddr::new(ipv4_mcast, config.port),
+            SocketAddr::V6(n) => SocketAddr::V6(net::ipv6_group_addr(ipv6_mcast, config.port, &n)),
+        };
+
+        let sock = conn.deconstruct();
+
+        match sock.send_to(&[], mcast_addr) {
+            Ok(_) => success = true,
+            Err(err) => debug!("Multicast from {} to {} failed: {}", local_addr, mcast_addr, err),
+        }
+    }
+
+    Ok(success)
+}
+
+/// Map the error of parsing a configured multicast group address.
+pub(crate) fn invalid_group<E>(_: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "Configured Multicast Address Is Invalid")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::net::{IpAddr, UdpSocket};
+    use std::time::{Duration, Instant};
+
+    use SSDPErrorKind;
+    use header::{HeaderRef, Host};
+    use message::{Config, MessageType, SearchResponse};
+    use message::ssdp::SSDPMessage;
+    use net::connector::UdpConnector;
+    use receiver::{FromRawSSDP, SSDPReceiver};
+    use super::{Backoff, SendSchedule};
+
+    /// Bind an IPv4 and an IPv6 loopback socket sharing a port, standing in
+    /// for the multicast groups of each family.
+    fn dual_stack_groups() -> (UdpSocket, UdpSocket) {
+        for _ in 0..10 {
+            let ipv4 = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let port = ipv4.local_addr().unwrap().port();
+
+            if let Ok(ipv6) = UdpSocket::bind(("::1", port)) {
+                return (ipv4, ipv6);
             }
         }
+
+        panic!("No port free on both IPv4 and IPv6 loopback");
     }
 
-    Ok(connectors)
+    fn millis(schedule: &SendSchedule) -> Vec<u64> {
+        (0..schedule.count()).map(|round| schedule.delay_before(round).as_millis() as u64).collect()
+    }
+
+    #[test]
+    fn positive_schedule_backoff() {
+        let schedule = SendSchedule::new(4).set_delay(Duration::from_millis(100));
+
+        assert_eq!(millis(&schedule), vec![0, 100, 100, 100]);
+        assert_eq!(millis(&schedule.set_backoff(Backoff::Linear)), vec![0, 100, 200, 300]);
+        assert_eq!(millis(&schedule.set_backoff(Backoff::Exponential)), vec![0, 100, 200, 400]);
+        assert_eq!(schedule.set_backoff(Backoff::Exponential).duration(), Duration::from_millis(700));
+    }
+
+    #[test]
+    fn positive_schedule_zero_count() {
+        assert_eq!(SendSchedule::new(0).count(), 1);
+        assert_eq!(SendSchedule::new(0).duration(), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn positive_schedule_saturates() {
+        let delay = Duration::from_secs(u64::MAX / 2 + 1);
+        let schedule = SendSchedule::new(3).set_delay(delay).set_backoff(Backoff::Exponential);
+
+        assert_eq!(schedule.delay_before(2), Duration::MAX);
+        assert_eq!(schedule.duration(), Duration::MAX);
+    }
+
+    #[test]
+    fn negative_send_on_unknown_interface() {
+        let message = SSDPMessage::new(MessageType::Search);
+        // 203.0.113.0/24 is reserved for documentation and never assigned locally
+        let local_ip: IpAddr = "203.0.113.77".parse().unwrap();
+
+        let result = super::send_on(&message, &Config::new(), local_ip, &SendSchedule::new(1), None);
+
+        match result.map(|_| ()).unwrap_err().kind() {
+            &SSDPErrorKind::UnknownInterface(ip) => assert_eq!(ip, local_ip),
+            other => panic!("Expected An Unknown Interface Error, Found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn positive_send_rounds_dual_stack() {
+        let (ipv4_group, ipv6_group) = dual_stack_groups();
+        let config = Config::new()
+            .set_ipv4_addr("127.0.0.1")
+            .set_ipv6_addr("::1")
+            .set_port(ipv4_group.local_addr().unwrap().port());
+
+        let mut connectors = vec![UdpConnector::new(("127.0.0.1", 0), None).unwrap(),
+                                  UdpConnector::new(("::1", 0), None).unwrap()];
+        let message = SSDPMessage::new(MessageType::Search);
+        super::send_rounds(&message, &mut connectors, &config, &SendSchedule::new(1), None).unwrap();
+
+        // Each family receives the search on its own group and answers it
+        let mut buf = [0u8; 1500];
+        for &(group, host) in &[(&ipv4_group, "127.0.0.1"), (&ipv6_group, "[::1]")] {
+            let (len, src) = group.recv_from(&mut buf).unwrap();
+            let received = SSDPMessage::raw_ssdp(&buf[..len]).unwrap();
+
+            assert_eq!(received.get::<Host>().map(|host| &host.hostname[..]), Some(host));
+            group.send_to(b"HTTP/1.1 200 OK\r\n\r\n", src).unwrap();
+        }
+
+        let socks = connectors.into_iter().map(UdpConnector::deconstruct).collect();
+        let receiver = SSDPReceiver::<SearchResponse>::new(socks, Some(Duration::from_millis(200))).unwrap();
+        let mut sources: Vec<IpAddr> = receiver.into_iter().map(|(_, src)| src.ip()).collect();
+        sources.sort();
+
+        assert_eq!(sources, vec!["127.0.0.1".parse::<IpAddr>().unwrap(), "::1".parse().unwrap()]);
+    }
+
+    #[test]
+    fn positive_send_rounds_search_repeat() {
+        let message = SSDPMessage::new(MessageType::Search);
+        let config = Config::new().set_search_repeat(3);
+
+        let sent = super::send_rounds(&message, &mut [], &config, &config.search_schedule, None).unwrap();
+
+        assert_eq!(sent, 3);
+    }
+
+    #[test]
+    fn positive_send_rounds_deadline() {
+        let message = SSDPMessage::new(MessageType::Search);
+        let schedule = SendSchedule::new(3)
+            .set_delay(Duration::from_millis(100))
+            .set_backoff(Backoff::Exponential);
+        let start = Instant::now();
+
+        let sent = super::send_rounds(&message,
+                                      &mut [],
+                                      &Config::new(),
+                                      &schedule,
+                                      Some(start + Duration::from_millis(250)))
+            .unwrap();
+
+        assert_eq!(sent, 2);
+        assert!(start.elapsed() < Duration::from_millis(250));
+    }
+
+    #[test]
+    fn positive_send_rounds_past_deadline() {
+        let message = SSDPMessage::new(MessageType::Search);
+        let schedule = SendSchedule::new(3);
+
+        let sent = super::send_rounds(&message, &mut [], &Config::new(), &schedule, Some(Instant::now()))
+            .unwrap();
+
+        assert_eq!(sent, 1);
+    }
+
+    #[test]
+    #[ignore = "needs multicast on an IPv4 or IPv6 interface"]
+    fn positive_can_multicast() {
+        assert!(super::can_multicast(&Config::new()).unwrap());
+    }
+
+    #[test]
+    fn positive_invalid_group_error() {
+        let mut config = Config::new();
+        config.ipv6_addr = "not an address".to_owned();
+
+        assert_eq!(super::can_multicast(&config).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_can_multicast_invalid_group() {
+        let mut config = Config::new();
+        config.ipv4_addr = "not an address".to_owned();
+        config.ipv6_addr = "not an address".to_owned();
+
+        super::can_multicast(&config).unwrap();
+    }
 }
@@ -1,10 +1,11 @@
 use std::borrow::Cow;
-use std::fmt::Debug;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::net::{ToSocketAddrs, UdpSocket};
 
 use hyper::header::{Header, HeaderFormat};
 
-use error::SSDPResult;
-use header::{HeaderRef, HeaderMut};
+use error::{SSDPErrorKind, SSDPResult};
+use header::{HeaderRef, HeaderMut, CacheControl, CacheDirective, Location, NT, NTS, TargetType, USN};
 use message::{MessageType, Listen, Config};
 use message::ssdp::SSDPMessage;
 use message::multicast::{self, Multicast};
@@ -22,8 +23,76 @@ impl NotifyMessage {
     pub fn new() -> Self {
         NotifyMessage { message: SSDPMessage::new(MessageType::Notify) }
     }
+
+    /// Construct a new `ssdp:alive` NotifyMessage announcing that the given
+    /// target is available for `max_age` seconds at the given location.
+    pub fn alive(nt: NT, usn: USN, location: Location, max_age: u32) -> NotifyMessage {
+        let mut notify = NotifyMessage::new();
+
+        notify.set(nt);
+        notify.set(NTS::Alive);
+        notify.set(usn);
+        notify.set(location);
+        notify.set(CacheControl(vec![CacheDirective::MaxAge(max_age)]));
+
+        notify
+    }
+
+    /// Construct a new `ssdp:byebye` NotifyMessage announcing that the given
+    /// target is leaving the network.
+    pub fn byebye(nt: NT, usn: USN) -> NotifyMessage {
+        let mut notify = NotifyMessage::new();
+
+        notify.set(nt);
+        notify.set(NTS::ByeBye);
+        notify.set(usn);
+
+        notify
+    }
+
+    /// Get the notification sub type from the `NTS` header.
+    ///
+    /// Returns `None` if the header is missing or holds an unknown sub type.
+    pub fn subtype(&self) -> Option<NTS> {
+        self.get::<NTS>().cloned()
+    }
+
+    /// Get the category of the target this message notifies about, from its
+    /// `NT` header.
+    pub fn target(&self) -> Option<TargetType> {
+        self.get::<NT>().map(TargetType::from)
+    }
+
+    /// Get the number of seconds this advertisement is valid for, from the
+    /// max-age directive of its `CACHE-CONTROL` header.
+    pub fn max_age(&self) -> Option<u32> {
+        self.message.max_age()
+    }
+
+    /// Get the request line of this message, `NOTIFY * HTTP/1.1`.
+    pub fn start_line(&self) -> String {
+        self.message.start_line()
+    }
+
+    /// Send this message to a single host, or a multicast group, over an
+    /// existing socket.
+    pub fn send_from<A: ToSocketAddrs>(&self, sock: &UdpSocket, dst_addr: A) -> SSDPResult<()> {
+        self.message.send_from(sock, dst_addr, &Default::default())
+    }
+
+    /// Get the exact bytes this message was received as, if it was received.
+    pub fn raw_bytes(&self) -> Option<&[u8]> {
+        self.message.raw_bytes()
+    }
+
+    /// Serialize this message into the bytes of a single SSDP datagram.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.message.to_bytes()
+    }
 }
 
+/// Announces the message out of every local interface allowed by the config,
+/// as a device does to advertise itself.
 impl Multicast for NotifyMessage {
     type Item = ();
 
@@ -44,13 +113,19 @@ impl FromRawSSDP for NotifyMessage {
         let message = try!(SSDPMessage::raw_ssdp(bytes));
 
         if message.message_type() != MessageType::Notify {
-            try!(Err("SSDP Message Received Is Not A NotifyMessage"))
+            Err(SSDPErrorKind::WrongMessageType(MessageType::Notify, message.message_type()).into())
         } else {
             Ok(NotifyMessage { message: message })
         }
     }
 }
 
+impl Display for NotifyMessage {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.message, f)
+    }
+}
+
 impl HeaderRef for NotifyMessage {
     fn get<H>(&self) -> Option<&H>
         where H: Header + HeaderFormat
@@ -87,6 +162,10 @@ impl Listen for NotifyListener {
 #[cfg(test)]
 mod tests {
     use super::NotifyMessage;
+    use {FieldMap, SSDPErrorKind};
+    use header::{HeaderRef, BootID, CacheControl, CacheDirective, ConfigID, Location, MaxAge, NextBootID, NT, NTS,
+                 SearchPort, USN};
+    use message::MessageType;
     use receiver::FromRawSSDP;
 
     #[test]
@@ -96,6 +175,14 @@ mod tests {
         NotifyMessage::raw_ssdp(raw_message.as_bytes()).unwrap();
     }
 
+    #[test]
+    fn positive_notify_start_line() {
+        let raw_message = "NOTIFY * HTTP/1.1\r\nHOST: 192.168.1.1\r\n\r\n";
+
+        let notify = NotifyMessage::raw_ssdp(raw_message.as_bytes()).unwrap();
+        assert_eq!(notify.start_line(), "NOTIFY * HTTP/1.1");
+    }
+
     #[test]
     #[should_panic]
     fn negative_search_message_type() {
@@ -105,10 +192,93 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn negative_response_message_type() {
         let raw_message = "HTTP/1.1 200 OK\r\n\r\n";
 
-        NotifyMessage::raw_ssdp(raw_message.as_bytes()).unwrap();
+        match NotifyMessage::raw_ssdp(raw_message.as_bytes()).unwrap_err().kind() {
+            &SSDPErrorKind::WrongMessageType(MessageType::Notify, MessageType::Response) => (),
+            other => panic!("Expected A Wrong Message Type Error, Found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn positive_alive_round_trip() {
+        let nt = NT(FieldMap::upnp("rootdevice"));
+        let usn = USN(FieldMap::uuid("device-UUID"), Some(FieldMap::upnp("rootdevice")));
+        let location = Location("http://192.168.1.1:8080/description.xml".to_owned());
+
+        let sent = NotifyMessage::alive(nt.clone(), usn.clone(), location.clone(), 1800);
+        let received = NotifyMessage::raw_ssdp(&sent.to_bytes()[..]).unwrap();
+
+        assert_eq!(received.get::<NT>(), Some(&nt));
+        assert_eq!(received.get::<NTS>(), Some(&NTS::Alive));
+        assert_eq!(received.get::<USN>(), Some(&usn));
+        assert_eq!(received.get::<Location>(), Some(&location));
+        assert_eq!(received.get::<CacheControl>(),
+                   Some(&CacheControl(vec![CacheDirective::MaxAge(1800)])));
+        assert_eq!(received.get::<MaxAge>(), Some(&MaxAge(1800)));
+    }
+
+    #[test]
+    fn positive_subtype() {
+        let subtype = |nts: &str| {
+            let raw_message = format!("NOTIFY * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nNTS: {}\r\n\r\n",
+                                      nts);
+
+            NotifyMessage::raw_ssdp(raw_message.as_bytes()).unwrap().subtype()
+        };
+
+        assert_eq!(subtype("ssdp:alive"), Some(NTS::Alive));
+        assert_eq!(subtype("ssdp:byebye"), Some(NTS::ByeBye));
+        assert_eq!(subtype("ssdp:update"), Some(NTS::Update));
+        assert_eq!(subtype("ssdp:unknown"), None);
+        assert_eq!(NotifyMessage::new().subtype(), None);
+    }
+
+    #[test]
+    fn positive_typed_max_age() {
+        let raw_message = "NOTIFY * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\n\
+                           CACHE-CONTROL: no-cache, Max-Age=120\r\n\r\n";
+
+        let message = NotifyMessage::raw_ssdp(raw_message.as_bytes()).unwrap();
+
+        assert_eq!(message.get::<MaxAge>(), Some(&MaxAge(120)));
+        assert_eq!(message.max_age(), Some(120));
+    }
+
+    #[test]
+    fn positive_typed_upnp_org_headers() {
+        let raw_message = "NOTIFY * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nNTS: ssdp:update\r\n\
+                           BOOTID.UPNP.ORG: 7\r\nNEXTBOOTID.UPNP.ORG: 8\r\nCONFIGID.UPNP.ORG: 3\r\n\
+                           SEARCHPORT.UPNP.ORG: 49152\r\n\r\n";
+
+        let message = NotifyMessage::raw_ssdp(raw_message.as_bytes()).unwrap();
+
+        assert_eq!(message.get::<BootID>(), Some(&BootID(7)));
+        assert_eq!(message.get::<NextBootID>(), Some(&NextBootID(8)));
+        assert_eq!(message.get::<ConfigID>(), Some(&ConfigID(3)));
+        assert_eq!(message.get::<SearchPort>(), Some(&SearchPort(49152)));
+    }
+
+    #[test]
+    fn negative_non_numeric_bootid() {
+        let raw_message = "NOTIFY * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nBOOTID.UPNP.ORG: seven\r\n\r\n";
+
+        let message = NotifyMessage::raw_ssdp(raw_message.as_bytes()).unwrap();
+
+        assert_eq!(message.get::<BootID>(), None);
+    }
+
+    #[test]
+    fn positive_byebye_round_trip() {
+        let nt = NT(FieldMap::urn("schemas-upnp-org:device:MediaServer:1"));
+        let usn = USN(FieldMap::uuid("device-UUID"), Some(FieldMap::urn("schemas-upnp-org:device:MediaServer:1")));
+
+        let sent = NotifyMessage::byebye(nt.clone(), usn.clone());
+        let received = NotifyMessage::raw_ssdp(&sent.to_bytes()[..]).unwrap();
+
+        assert_eq!(received.get::<NT>(), Some(&nt));
+        assert_eq!(received.get::<NTS>(), Some(&NTS::ByeBye));
+        assert_eq!(received.get::<USN>(), Some(&usn));
     }
 }
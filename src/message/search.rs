@@ -1,18 +1,24 @@
 use std::borrow::Cow;
-use std::fmt::Debug;
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Display, Formatter};
 use std::net::ToSocketAddrs;
-use std::time::Duration;
-use std::io;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use hyper::header::{Header, HeaderFormat};
 
+use PacketInfo;
 use error::SSDPResult;
-use header::{HeaderRef, HeaderMut, MX};
+use {FieldMap, SSDPErrorKind};
+use header::{HeaderRef, HeaderMut, CPFN, Man, MX, Server, ServerInfo, ST, TargetType, UserAgent, USN};
+use header::mx;
 use message::{self, MessageType, Listen, Config};
 use message::ssdp::SSDPMessage;
 use message::multicast::{self, Multicast};
-use receiver::{SSDPReceiver, FromRawSSDP};
+use receiver::{SSDPReceiver, SSDPFilter, SSDPIter, FromRawSSDP};
 use net;
+use net::connector::UdpConnector;
 
 
 /// Overhead to add to device response times to account for transport time.
@@ -21,6 +27,9 @@ const NETWORK_TIMEOUT_OVERHEAD: u8 = 1;
 /// Devices are required to respond within 1 second of receiving unicast message.
 const DEFAULT_UNICAST_TIMEOUT: u8 = 1 + NETWORK_TIMEOUT_OVERHEAD;
 
+/// Only status code a valid `SearchResponse` can carry.
+const VALID_RESPONSE_CODE: u16 = 200;
+
 /// Search request that can be sent via unicast or multicast to devices on the network.
 #[derive(Debug, Clone)]
 pub struct SearchRequest {
@@ -33,54 +42,331 @@ impl SearchRequest {
         SearchRequest { message: SSDPMessage::new(MessageType::Search) }
     }
 
+    /// Construct a new SearchRequest targeting the single device with the given uuid.
+    ///
+    /// The uuid is expected in its canonical `8-4-4-4-12` hexadecimal form,
+    /// without the `uuid:` prefix.
+    pub fn for_uuid(uuid: &str, mx: u8) -> SSDPResult<SearchRequest> {
+        if !is_valid_uuid(uuid) {
+            return Err(SSDPErrorKind::InvalidHeader("ST", "Supplied UUID Is Malformed").into());
+        }
+
+        let mut request = SearchRequest::new();
+        request.set(Man);
+        request.set(MX::new(mx)?);
+        request.set(ST::Target(FieldMap::uuid(uuid)));
+
+        Ok(request)
+    }
+
+    /// Set the `MX` header, the number of seconds devices may wait before
+    /// responding.
+    ///
+    /// Values outside of the 1 to 120 seconds allowed by the standard are
+    /// clamped into it. Values above the recommended 5 seconds are kept but
+    /// logged, since they only make sense when a very large number of devices
+    /// is expected to answer.
+    pub fn set_mx_seconds(&mut self, seconds: u8) {
+        let clamped = seconds.clamp(mx::MX_HEADER_MIN, mx::MX_HEADER_MAX);
+
+        if clamped != seconds {
+            warn!("MX of {} seconds is out of bounds, using {} instead", seconds, clamped);
+        } else if clamped > mx::MX_HEADER_RECOMMENDED_MAX {
+            warn!("MX of {} seconds is above the recommended maximum of {}",
+                  clamped,
+                  mx::MX_HEADER_RECOMMENDED_MAX);
+        }
+
+        self.set(MX(clamped));
+    }
+
+    /// Get a random delay to wait before responding to this request, between
+    /// zero and its `MX` header.
+    ///
+    /// Returns `None` if the request has no `MX` header, as is the case for
+    /// unicast searches which should be answered right away.
+    pub fn response_delay(&self) -> Option<Duration> {
+        self.get::<MX>().map(MX::response_delay)
+    }
+
+    /// Describe the control point that sent this request, for logging.
+    ///
+    /// Combines the `CPFN.UPNP.ORG` friendly name with the `USER-AGENT` header,
+    /// as in `Living Room TV (Linux/4.9 UPnP/2.0 Player/1.0)`. Returns `None`
+    /// when neither header is present.
+    pub fn client_description(&self) -> Option<String> {
+        match (self.get::<CPFN>(), self.get::<UserAgent>()) {
+            (Some(CPFN(name)), Some(UserAgent(agent))) => Some(format!("{} ({})", name, agent)),
+            (Some(CPFN(name)), None) => Some(name.clone()),
+            (None, Some(UserAgent(agent))) => Some(agent.clone()),
+            (None, None) => None,
+        }
+    }
+
+    /// Get the parts of the `USER-AGENT` header of this request.
+    ///
+    /// Returns `None` if the header is missing or not of the standard form.
+    pub fn user_agent_info(&self) -> Option<ServerInfo> {
+        self.get::<UserAgent>().and_then(|agent| ServerInfo::parse(&agent.0))
+    }
+
+    /// Serialize this request into the bytes of a single SSDP datagram.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.message.to_bytes()
+    }
+
+    /// Get the exact bytes this request was received as, if it was received.
+    pub fn raw_bytes(&self) -> Option<&[u8]> {
+        self.message.raw_bytes()
+    }
+
+    /// Serialize this request as it would be sent under the given config.
+    pub(crate) fn prepared_bytes(&self, config: &Config) -> SSDPResult<Vec<u8>> {
+        let prepared = self.message.prepare(config)?;
+        prepared.check_headers()?;
+
+        Ok(prepared.to_bytes())
+    }
+
+    /// Send this search request to a single host over an existing socket.
+    ///
+    /// Responses arrive on the same socket, for example through
+    /// `SSDPReceiver::from_socket()`.
+    pub fn send_from<A: ToSocketAddrs>(&self, sock: &UdpSocket, dst_addr: A) -> SSDPResult<()> {
+        self.message.send_from(sock, dst_addr, &Default::default())
+    }
+
     /// Send this search request to a single host.
     ///
+    /// This will call `unicast_with_config()` with _default_ values.
+    pub fn unicast<A: ToSocketAddrs>(&mut self, dst_addr: A) -> SSDPResult<SSDPReceiver<SearchResponse>> {
+        self.unicast_with_config(dst_addr, &Default::default())
+    }
+
+    /// Send this search request to a single host.
+    ///
+    /// The destination is the first address that `dst_addr` resolves to which
+    /// is allowed by the `IpVersionMode` of the config.
+    ///
     /// Currently this sends the unicast message on all available network
     /// interfaces. This assumes that the network interfaces are operating
     /// on either different subnets or different ip address ranges.
-    pub fn unicast<A: ToSocketAddrs>(&mut self, dst_addr: A) -> SSDPResult<SSDPReceiver<SearchResponse>> {
-        let mode = try!(net::IpVersionMode::from_addr(&dst_addr));
-        let mut connectors = try!(message::all_local_connectors(None, &mode));
+    pub fn unicast_with_config<A: ToSocketAddrs>(&mut self,
+                                                 dst_addr: A,
+                                                 config: &Config)
+                                                 -> SSDPResult<SSDPReceiver<SearchResponse>> {
+        let deadline = config.receive_timeout.map(|n| Instant::now() + n);
+        let raw_connectors = self.unicast_sockets(dst_addr, config)?;
+        let opt_timeout = if deadline.is_some() { None } else { opt_unicast_timeout(self.get::<MX>()) };
+        let filter = response_filter(config, self.get::<ST>());
+
+        Ok(SSDPReceiver::with_deadline(raw_connectors, opt_timeout, deadline, filter)?)
+    }
+
+    /// Send this search request to a single host and wait for its first response.
+    ///
+    /// Returns `None` if no response arrives within the timeout. The receiving
+    /// sockets are closed as soon as this returns, which makes this a cheap
+    /// check of whether a known device is still online.
+    pub fn probe<A: ToSocketAddrs>(&self,
+                                   dst_addr: A,
+                                   timeout: Duration)
+                                   -> SSDPResult<Option<SearchResponse>> {
+        let config = Config::default();
+        let raw_connectors = self.unicast_sockets(dst_addr, &config)?;
+        let filter = response_filter(&config, self.get::<ST>());
+        let receiver = SSDPReceiver::with_filter(raw_connectors, Some(timeout), filter)?;
+
+        Ok(receiver.recv_timeout(timeout).ok().map(|(response, _)| response))
+    }
+
+    /// Send this search request to a single host on all local connectors,
+    /// returning the sockets to receive responses on.
+    fn unicast_sockets<A: ToSocketAddrs>(&self, dst_addr: A, config: &Config) -> SSDPResult<Vec<UdpSocket>> {
+        let prepared = self.message.prepare(config)?;
+
+        let dst_addr = net::addr_from_trait_with_mode(dst_addr, &config.mode)?;
+        let mode = net::IpVersionMode::from_addr(dst_addr)?;
+        let mut connectors =
+            message::all_local_connectors(|_| None,
+                                          &mode,
+                                          config.bind_retries,
+                                          config.send_global_ipv6,
+                                          &config.interfaces)?;
 
         // Send On All Connectors
         for connector in &mut connectors {
-            try!(self.message.send(connector, &dst_addr));
+            prepared.send(connector, dst_addr)?;
         }
 
-        let mut raw_connectors = Vec::with_capacity(connectors.len());
-        raw_connectors.extend(connectors.into_iter().map(|conn| conn.deconstruct()));
+        Ok(connectors.into_iter().map(|conn| conn.deconstruct()).collect())
+    }
+}
+
+impl SearchRequest {
+    /// Send this search request to the multicast group out of the interface
+    /// with the given local address only.
+    ///
+    /// This will call `multicast_on_with_config()` with _default_ values.
+    pub fn multicast_on(&self, local_ip: IpAddr) -> SSDPResult<SSDPReceiver<SearchResponse>> {
+        self.multicast_on_with_config(local_ip, &Default::default())
+    }
+
+    /// Send this search request to the multicast group out of the interface
+    /// with the given local address only.
+    ///
+    /// Fails with `UnknownInterface` if no local interface has the address.
+    pub fn multicast_on_with_config(&self,
+                                    local_ip: IpAddr,
+                                    config: &Config)
+                                    -> SSDPResult<SSDPReceiver<SearchResponse>> {
+        let (connectors, mcast_timeout, deadline) = self.send_search(config, |deadline| {
+            multicast::send_on(&self.message, config, local_ip, &config.search_schedule, deadline)
+        })?;
+
+        search_receiver(connectors, mcast_timeout, deadline, config, self.get::<ST>())
+    }
+
+    /// Send this search request to the multicast group, receiving responses
+    /// that fail to parse as errors instead of dropping them.
+    ///
+    /// This will call `multicast_results_with_config()` with _default_ values.
+    pub fn multicast_results(&self) -> SSDPResult<SSDPReceiver<SSDPResult<SearchResponse>>> {
+        self.multicast_results_with_config(&Default::default())
+    }
+
+    /// Send this search request as with `multicast_with_config()`, receiving
+    /// responses that fail to parse as errors instead of dropping them.
+    ///
+    /// Errors are passed on regardless of the config, only valid responses
+    /// are filtered.
+    pub fn multicast_results_with_config(&self,
+                                         config: &Config)
+                                         -> SSDPResult<SSDPReceiver<SSDPResult<SearchResponse>>> {
+        let (connectors, mcast_timeout, deadline) = self.send_search(config, |deadline| {
+            multicast::send_repeated(&self.message, config, &config.search_schedule, deadline)
+        })?;
+        let raw_connectors = connectors.into_iter().map(|conn| conn.deconstruct()).collect();
+
+        let filter = response_filter(config, self.get::<ST>());
+        let result_filter = move |result: &SSDPResult<SearchResponse>, src: &SocketAddr| match *result {
+            Ok(ref response) => filter(response, src),
+            Err(_) => true,
+        };
 
-        let opt_timeout = opt_unicast_timeout(self.get::<MX>());
+        Ok(SSDPReceiver::with_deadline(raw_connectors, mcast_timeout, deadline, Arc::new(result_filter))?)
+    }
+
+    /// Send this search request to the multicast group through the given
+    /// function, returning the connectors it was sent from together with how
+    /// long to receive responses on them.
+    ///
+    /// A receive timeout in the config is a hard deadline counted from the
+    /// first send, which the function is given. Otherwise responses are
+    /// received until none arrived for the `MX` based timeout.
+    fn send_search<F>(&self, config: &Config, send: F) -> SSDPResult<SentSearch>
+        where F: FnOnce(Option<Instant>) -> SSDPResult<Vec<UdpConnector>>
+    {
+        // A multicast search requires an MX header even if the config overrides the timeout
+        let mcast_timeout = multicast_timeout(self.get::<MX>())?;
+        let deadline = config.receive_timeout.map(|n| Instant::now() + n);
+
+        let connectors = send(deadline)?;
+        let idle_timeout = if deadline.is_some() { None } else { Some(mcast_timeout) };
 
-        Ok(try!(SSDPReceiver::new(raw_connectors, opt_timeout)))
+        Ok((connectors, idle_timeout, deadline))
     }
 }
 
+/// Connectors a multicast search was sent from, together with the idle
+/// timeout and the deadline for receiving its responses.
+type SentSearch = (Vec<UdpConnector>, Option<Duration>, Option<Instant>);
+
 impl Multicast for SearchRequest {
     type Item = SSDPReceiver<SearchResponse>;
 
     fn multicast_with_config(&self, config: &Config) -> SSDPResult<Self::Item> {
-        let connectors = multicast::send(&self.message, config)?;
+        let (connectors, mcast_timeout, deadline) = self.send_search(config, |deadline| {
+            multicast::send_repeated(&self.message, config, &config.search_schedule, deadline)
+        })?;
 
-        let mcast_timeout = try!(multicast_timeout(self.get::<MX>()));
-        let mut raw_connectors = Vec::with_capacity(connectors.len());
-        raw_connectors.extend(connectors.into_iter().map(|conn| conn.deconstruct()));
-
-        Ok(try!(SSDPReceiver::new(raw_connectors, Some(mcast_timeout))))
+        search_receiver(connectors, mcast_timeout, deadline, config, self.get::<ST>())
     }
 }
 
+/// Receive the responses to a multicast search on the connectors it was sent from.
+fn search_receiver(connectors: Vec<UdpConnector>,
+                   mcast_timeout: Option<Duration>,
+                   deadline: Option<Instant>,
+                   config: &Config,
+                   target: Option<&ST>)
+                   -> SSDPResult<SSDPReceiver<SearchResponse>> {
+    let raw_connectors = connectors.into_iter().map(|conn| conn.deconstruct()).collect();
+    let filter = response_filter(config, target);
+
+    Ok(SSDPReceiver::with_deadline(raw_connectors, mcast_timeout, deadline, filter)?)
+}
+
+/// Get the filter to apply to search responses received under the given config.
+///
+/// The `target` is the `ST` of the request, used if the config matches targets.
+fn response_filter(config: &Config, target: Option<&ST>) -> SSDPFilter<SearchResponse> {
+    let strict = config.strict;
+    let target = if config.match_target { target.cloned() } else { None };
+    let subnets = if config.same_subnet { local_subnets() } else { None };
+
+    Arc::new(move |response: &SearchResponse, src: &SocketAddr| {
+        if strict && response.status_code() != VALID_RESPONSE_CODE {
+            debug!("Dropping response from {} with status {}", src, response.status_code());
+            return false;
+        }
+
+        if let (Some(subnets), Some(info)) = (subnets.as_ref(), response.packet_info()) {
+            if !message::is_same_subnet(subnets, info) {
+                info!("Dropping response from {} outside the subnet of {}", src, info.local_addr);
+                return false;
+            }
+        }
+
+        match (target.as_ref(), response.get::<ST>()) {
+            (Some(ST::Target(expected)), Some(ST::Target(found))) if expected != found => {
+                debug!("Dropping response from {} for target {}, expected {}", src, found, expected);
+                false
+            }
+            _ => true,
+        }
+    })
+}
+
+/// Get the subnets of all local interfaces, or `None` if they can not be read.
+fn local_subnets() -> Option<Vec<message::LocalSubnet>> {
+    message::get_local_subnets()
+        .map_err(|err| warn!("Not filtering responses by subnet: {}", err))
+        .ok()
+}
+
 impl Default for SearchRequest {
     fn default() -> Self {
         SearchRequest::new()
     }
 }
 
+/// Check if the given string is a uuid in its canonical hexadecimal form.
+fn is_valid_uuid(uuid: &str) -> bool {
+    let groups: Vec<&str> = uuid.split('-').collect();
+    let lengths = [8, 4, 4, 4, 12];
+
+    groups.len() == lengths.len() &&
+    groups.iter()
+        .zip(lengths.iter())
+        .all(|(group, &len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
 /// Get the require timeout to use for a multicast search request.
 fn multicast_timeout(mx: Option<&MX>) -> SSDPResult<Duration> {
     match mx {
         Some(&MX(n)) => Ok(Duration::new((n + NETWORK_TIMEOUT_OVERHEAD) as u64, 0)),
-        None => try!(Err("Multicast Searches Require An MX Header")),
+        None => Err(SSDPErrorKind::MissingHeader(MX::header_name()).into()),
     }
 }
 
@@ -97,13 +383,19 @@ impl FromRawSSDP for SearchRequest {
         let message = try!(SSDPMessage::raw_ssdp(bytes));
 
         if message.message_type() != MessageType::Search {
-            try!(Err("SSDP Message Received Is Not A SearchRequest"))
+            Err(SSDPErrorKind::WrongMessageType(MessageType::Search, message.message_type()).into())
         } else {
             Ok(SearchRequest { message: message })
         }
     }
 }
 
+impl Display for SearchRequest {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.message, f)
+    }
+}
+
 impl HeaderRef for SearchRequest {
     fn get<H>(&self) -> Option<&H>
         where H: Header + HeaderFormat
@@ -134,39 +426,161 @@ impl HeaderMut for SearchRequest {
 #[derive(Debug, Clone)]
 pub struct SearchResponse {
     message: SSDPMessage,
+    info: Option<PacketInfo>,
 }
 
 impl SearchResponse {
     /// Construct a new SearchResponse.
     pub fn new() -> SearchResponse {
-        SearchResponse { message: SSDPMessage::new(MessageType::Response) }
+        SearchResponse {
+            message: SSDPMessage::new(MessageType::Response),
+            info: None,
+        }
+    }
+
+    /// Get information about the packet this response was received in.
+    ///
+    /// This includes the interface the response arrived on, where the platform
+    /// reports it. Otherwise the local address of the receiving socket is the
+    /// best indication of the interface. Responses that were constructed
+    /// locally have no packet info.
+    pub fn packet_info(&self) -> Option<&PacketInfo> {
+        self.info.as_ref()
+    }
+
+    /// Get the address this response was sent from.
+    ///
+    /// This is the peer to contact the device at, which can differ from the
+    /// host in its `LOCATION` header. Responses that were constructed locally
+    /// have no remote address.
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.info.as_ref().map(|info| info.src_addr)
+    }
+
+    /// Get the time at which this response was read from the network.
+    ///
+    /// Responses read from a single socket are yielded in the order they
+    /// arrived, so their times are non-decreasing. Responses from different
+    /// sockets of the same `SSDPReceiver` can be yielded out of order. Responses
+    /// that were constructed locally have no arrival time.
+    pub fn arrived_at(&self) -> Option<Instant> {
+        self.info.map(|info| info.arrived_at)
+    }
+
+    /// Get the number of seconds this response is valid for, from the max-age
+    /// directive of its `CACHE-CONTROL` header.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ssdp::header::{CacheControl, CacheDirective, HeaderMut};
+    /// use ssdp::message::SearchResponse;
+    ///
+    /// let mut response = SearchResponse::new();
+    /// assert_eq!(response.max_age(), None);
+    ///
+    /// response.set(CacheControl(vec![CacheDirective::MaxAge(1800)]));
+    /// assert_eq!(response.max_age(), Some(1800));
+    /// ```
+    pub fn max_age(&self) -> Option<u32> {
+        self.message.max_age()
+    }
+
+    /// Get the category of the search target this response answers, from its
+    /// `ST` header.
+    pub fn target(&self) -> Option<TargetType> {
+        self.get::<ST>().map(TargetType::from)
+    }
+
+    /// Get the time at which this response expires, measured from when it
+    /// arrived.
+    ///
+    /// Responses that were constructed locally or have no max-age never expire.
+    pub fn expires_at(&self) -> Option<Instant> {
+        match (self.arrived_at(), self.max_age()) {
+            (Some(arrived_at), Some(secs)) => Some(arrived_at + Duration::from_secs(u64::from(secs))),
+            _ => None,
+        }
+    }
+
+    /// Get the status code this response was sent with.
+    ///
+    /// Anything other than a 200 indicates a misbehaving device.
+    pub fn status_code(&self) -> u16 {
+        self.message.status_code().unwrap_or(VALID_RESPONSE_CODE)
+    }
+
+    /// Get the parts of the `SERVER` header of this response.
+    ///
+    /// Returns `None` if the header is missing or not of the standard form.
+    pub fn server_info(&self) -> Option<ServerInfo> {
+        self.get::<Server>().and_then(|server| ServerInfo::parse(&server.0))
+    }
+
+    /// Serialize this response into the bytes of a single SSDP datagram.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.message.to_bytes()
+    }
+
+    /// Get the exact bytes this response was received as, if it was received.
+    pub fn raw_bytes(&self) -> Option<&[u8]> {
+        self.message.raw_bytes()
+    }
+
+    /// Get the status line this response was sent with, such as `HTTP/1.1 200 OK`.
+    pub fn start_line(&self) -> String {
+        self.message.start_line()
+    }
+
+    /// Get the reason phrase this response was sent with.
+    ///
+    /// This is purely informational; devices are free to send any phrase.
+    pub fn status_reason(&self) -> Option<&str> {
+        self.message.status_reason()
+    }
+
+    /// Send this search response to a single host over an existing socket.
+    pub fn send_from<A: ToSocketAddrs>(&self, sock: &UdpSocket, dst_addr: A) -> SSDPResult<()> {
+        self.message.send_from(sock, dst_addr, &Default::default())
     }
 
     /// Send this search response to a single host.
     ///
-    /// Currently this sends the unicast message on all available network
-    /// interfaces. This assumes that the network interfaces are operating
-    /// on either different subnets or different ip address ranges.
-    pub fn unicast<A: ToSocketAddrs>(&mut self, dst_addr: A) -> SSDPResult<()> {
+    /// Currently this sends the unicast message from an ephemeral port on all
+    /// available network interfaces. This assumes that the network interfaces
+    /// are operating on either different subnets or different ip address ranges.
+    ///
+    /// Succeeds if the response could be sent on at least one interface,
+    /// otherwise the error from the last interface is returned.
+    pub fn unicast<A: ToSocketAddrs>(&self, dst_addr: A) -> SSDPResult<()> {
+        let config = Config::default();
         let mode = try!(net::IpVersionMode::from_addr(&dst_addr));
-        let mut connectors = try!(message::all_local_connectors(None, &mode));
+        let mut connectors =
+            message::all_local_connectors(|_| None,
+                                          &mode,
+                                          config.bind_retries,
+                                          config.send_global_ipv6,
+                                          &config.interfaces)?;
+        let prepared = self.message.prepare(&config)?;
 
-        let mut success_count = 0;
-        let mut error_count = 0;
+        let mut sent = false;
+        let mut last_error = None;
         // Send On All Connectors
         for conn in &mut connectors {
             // Some routing errors are expected, not all interfaces can find the target addresses
-            match self.message.send(conn, &dst_addr) {
-                Ok(_) => success_count += 1,
-                Err(_) => error_count += 1,
+            match prepared.send(conn, &dst_addr) {
+                Ok(_) => sent = true,
+                Err(e) => {
+                    debug!("Failed to send search response to {:?}: {}", conn.local_addr(), e);
+                    last_error = Some(e);
+                }
             }
         }
 
-        if success_count == 0 && error_count > 0 {
-            try!(Err(io::Error::last_os_error()));
+        match last_error {
+            Some(e) if !sent => Err(e),
+            _ => Ok(()),
         }
-
-        Ok(())
     }
 }
 
@@ -176,6 +590,51 @@ impl Default for SearchResponse {
     }
 }
 
+impl SSDPReceiver<SearchResponse> {
+    /// Iterate over the responses, skipping any with the same `USN` header as
+    /// an earlier one.
+    ///
+    /// Responses without a `USN` header are told apart by their source address.
+    pub fn dedup_by_usn(self) -> UniqueResponses {
+        UniqueResponses {
+            iter: self.into_iter(),
+            seen: HashSet::new(),
+        }
+    }
+}
+
+/// Identity of a search response used to detect duplicates.
+#[derive(PartialEq, Eq, Hash)]
+enum ResponseKey {
+    Usn(USN),
+    Src(SocketAddr),
+}
+
+/// Iterator over the first search response from each service.
+pub struct UniqueResponses {
+    iter: SSDPIter<SearchResponse>,
+    seen: HashSet<ResponseKey>,
+}
+
+impl Iterator for UniqueResponses {
+    type Item = (SearchResponse, SocketAddr);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (response, src) in &mut self.iter {
+            let key = match response.get::<USN>() {
+                Some(usn) => ResponseKey::Usn(usn.clone()),
+                None => ResponseKey::Src(src),
+            };
+
+            if self.seen.insert(key) {
+                return Some((response, src));
+            }
+        }
+
+        None
+    }
+}
+
 /// Search listener that can listen for search messages sent within the network.
 pub struct SearchListener;
 
@@ -188,11 +647,27 @@ impl FromRawSSDP for SearchResponse {
         let message = try!(SSDPMessage::raw_ssdp(bytes));
 
         if message.message_type() != MessageType::Response {
-            try!(Err("SSDP Message Received Is Not A SearchResponse"))
+            Err(SSDPErrorKind::WrongMessageType(MessageType::Response, message.message_type()).into())
         } else {
-            Ok(SearchResponse { message: message })
+            Ok(SearchResponse {
+                message: message,
+                info: None,
+            })
         }
     }
+
+    fn raw_ssdp_with_info(bytes: &[u8], info: &PacketInfo) -> SSDPResult<SearchResponse> {
+        let mut response = SearchResponse::raw_ssdp(bytes)?;
+        response.info = Some(*info);
+
+        Ok(response)
+    }
+}
+
+impl Display for SearchResponse {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.message, f)
+    }
 }
 
 impl HeaderRef for SearchResponse {
@@ -223,7 +698,20 @@ impl HeaderMut for SearchResponse {
 
 #[cfg(test)]
 mod tests {
-    use header::MX;
+    use std::net::{IpAddr, UdpSocket};
+    use std::cell::Cell;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use std::net::SocketAddr;
+
+    use {FieldMap, PacketInfo, SSDPError, SSDPErrorKind};
+    use header::{HeaderMut, HeaderRef, Man, MX, ST, TargetType, UserAgent, USN};
+    use message::{self, Config, InterfaceSet, MessageType, Multicast};
+    use net::{self, IpVersionMode};
+    use net::connector::UdpConnector;
+    use receiver::{SSDPReceiver, FromRawSSDP};
+    use super::{SearchRequest, SearchResponse};
 
     #[test]
     fn positive_multicast_timeout() {
@@ -241,8 +729,601 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn negative_multicast_timeout() {
-        super::multicast_timeout(None).unwrap();
+        match super::multicast_timeout(None).unwrap_err().kind() {
+            &SSDPErrorKind::MissingHeader("MX") => (),
+            other => panic!("Expected A Missing Header Error, Found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn positive_configured_receive_timeout() {
+        let config = Config::new().set_receive_timeout(Duration::from_secs(10));
+        let mut request = SearchRequest::new();
+        request.set(MX(1));
+
+        let (_, idle, deadline) = request.send_search(&config, |_| Ok(Vec::new())).unwrap();
+        let remaining = deadline.unwrap().saturating_duration_since(Instant::now());
+        assert!(remaining <= Duration::from_secs(10) && remaining > Duration::from_secs(9));
+        assert_eq!(idle, None);
+
+        let (_, idle, deadline) = request.send_search(&Config::new(), |_| Ok(Vec::new())).unwrap();
+        assert_eq!(idle, Some(Duration::from_secs(2)));
+        assert_eq!(deadline, None);
+    }
+
+    #[test]
+    fn positive_receive_timeout_covers_sends() {
+        let config = Config::new().set_receive_timeout(Duration::from_millis(300));
+        let mut request = SearchRequest::new();
+        request.set(MX(1));
+
+        let (_, _, deadline) = request.send_search(&config, |deadline| {
+                assert!(deadline.is_some());
+                thread::sleep(Duration::from_millis(200));
+                Ok(Vec::new())
+            })
+            .unwrap();
+
+        assert!(deadline.unwrap().saturating_duration_since(Instant::now()) <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn positive_receive_timeout_ends_during_responses() {
+        let config = Config::new().set_receive_timeout(Duration::from_millis(300));
+        let mut request = SearchRequest::new();
+        request.set(MX(1));
+
+        let local = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let local_addr = local.local_addr().unwrap();
+        let (connectors, idle, deadline) = request.send_search(&config, |_| {
+                Ok(vec![UdpConnector::from_socket(local)])
+            })
+            .unwrap();
+        let receiver = super::search_receiver(connectors, idle, deadline, &config, None).unwrap();
+
+        // Keep responses arriving well past the deadline
+        let device = thread::spawn(move || {
+            let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let started = Instant::now();
+
+            while started.elapsed() < Duration::from_secs(2) {
+                let _ = sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", local_addr);
+                thread::sleep(Duration::from_millis(20));
+            }
+        });
+
+        let started = Instant::now();
+        assert!(receiver.into_iter().count() > 0);
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        device.join().unwrap();
+    }
+
+    #[test]
+    fn negative_send_search_missing_mx() {
+        let sent = Cell::new(false);
+
+        let result = SearchRequest::new().send_search(&Config::new(), |_| {
+            sent.set(true);
+            Ok(Vec::new())
+        });
+
+        assert!(result.is_err());
+        assert!(!sent.get());
+    }
+
+    #[test]
+    fn positive_unicast_receive_timeout() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let config = Config::new().set_receive_timeout(Duration::from_millis(200));
+
+        let mut request = SearchRequest::new();
+        request.set(Man);
+        request.set(MX(5));
+        request.set(ST::All);
+
+        let started = Instant::now();
+        let receiver = request.unicast_with_config(silent.local_addr().unwrap(), &config).unwrap();
+
+        assert_eq!(receiver.into_iter().count(), 0);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(request.get::<MX>(), Some(&MX(5)));
+    }
+
+    #[test]
+    fn positive_set_mx_seconds_clamped() {
+        let mut request = SearchRequest::new();
+
+        request.set_mx_seconds(0);
+        assert_eq!(request.get::<MX>(), Some(&MX(1)));
+
+        request.set_mx_seconds(200);
+        assert_eq!(request.get::<MX>(), Some(&MX(120)));
+
+        request.set_mx_seconds(3);
+        assert_eq!(request.get::<MX>(), Some(&MX(3)));
+    }
+
+    #[test]
+    fn positive_response_delay() {
+        let mut request = SearchRequest::new();
+        assert_eq!(request.response_delay(), None);
+
+        request.set_mx_seconds(2);
+        for _ in 0..100 {
+            assert!(request.response_delay().unwrap() <= Duration::from_secs(2));
+        }
+    }
+
+    #[test]
+    fn negative_wrong_message_type() {
+        let raw_request = "M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\n\r\n";
+
+        match SearchResponse::raw_ssdp(raw_request.as_bytes()).unwrap_err().kind() {
+            &SSDPErrorKind::WrongMessageType(MessageType::Response, MessageType::Search) => (),
+            other => panic!("Expected A Wrong Message Type Error, Found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn positive_ok_status_code() {
+        let raw_response = "HTTP/1.1 200 OK\r\n\r\n";
+
+        let response = SearchResponse::raw_ssdp(raw_response.as_bytes()).unwrap();
+        assert_eq!(response.status_code(), 200);
+    }
+
+    #[test]
+    fn positive_error_status_code() {
+        let raw_response = "HTTP/1.1 404 Not Found\r\n\r\n";
+
+        let response = SearchResponse::raw_ssdp(raw_response.as_bytes()).unwrap();
+        assert_eq!(response.status_code(), 404);
+    }
+
+    #[test]
+    fn positive_custom_status_reason() {
+        let raw_response = "HTTP/1.1 200 Everything Is Fine\r\n\r\n";
+
+        let response = SearchResponse::raw_ssdp(raw_response.as_bytes()).unwrap();
+        assert_eq!(response.status_reason(), Some("Everything Is Fine"));
+    }
+
+    #[test]
+    fn positive_send_from_socket() {
+        let requester = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let responder = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let requests = SSDPReceiver::<SearchRequest>::from_socket(responder.try_clone().unwrap(),
+                                                                  Some(Duration::from_secs(2)))
+            .unwrap();
+        let responses = SSDPReceiver::<SearchResponse>::from_socket(requester.try_clone().unwrap(),
+                                                                    Some(Duration::from_secs(2)))
+            .unwrap();
+
+        let mut request = SearchRequest::new();
+        request.set(ST::All);
+        request.send_from(&requester, responder.local_addr().unwrap()).unwrap();
+
+        let (_, src) = requests.recv().unwrap();
+        assert_eq!(src, requester.local_addr().unwrap());
+
+        SearchResponse::new().send_from(&responder, src).unwrap();
+
+        let (response, src) = responses.recv().unwrap();
+        assert_eq!(response.status_code(), 200);
+        assert_eq!(src, responder.local_addr().unwrap());
+    }
+
+    #[test]
+    fn positive_response_raw_bytes() {
+        let raw_response = "HTTP/1.1 200 OK\r\nst:  ssdp:all\r\n\r\n";
+
+        let mut response = SearchResponse::raw_ssdp(raw_response.as_bytes()).unwrap();
+        assert_eq!(response.raw_bytes(), Some(raw_response.as_bytes()));
+        assert_ne!(&response.to_bytes()[..], raw_response.as_bytes());
+
+        response.set(MX(5));
+        assert_eq!(response.raw_bytes(), None);
+        assert_eq!(SearchResponse::new().raw_bytes(), None);
+    }
+
+    #[test]
+    fn positive_response_server_info() {
+        let raw_response = "HTTP/1.1 200 OK\r\nSERVER: Linux/4.9 UPnP/1.1 Sonos/52.1\r\n\r\n";
+
+        let response = SearchResponse::raw_ssdp(raw_response.as_bytes()).unwrap();
+        assert_eq!(response.server_info().map(|info| info.product_token().to_owned()),
+                   Some("Sonos/52.1".to_owned()));
+        assert_eq!(SearchResponse::new().server_info(), None);
+    }
+
+    #[test]
+    fn positive_response_target() {
+        let raw_response = "HTTP/1.1 200 OK\r\nST: urn:schemas-upnp-org:device:MediaServer:1\r\n\r\n";
+
+        let response = SearchResponse::raw_ssdp(raw_response.as_bytes()).unwrap();
+        assert_eq!(response.target(),
+                   Some(TargetType::Urn("schemas-upnp-org:device:MediaServer:1".to_owned())));
+        assert_eq!(SearchResponse::new().target(), None);
+    }
+
+    #[test]
+    fn positive_response_start_line() {
+        let raw_response = "HTTP/1.1 404 Not Found\r\n\r\n";
+
+        let response = SearchResponse::raw_ssdp(raw_response.as_bytes()).unwrap();
+        assert_eq!(response.start_line(), "HTTP/1.1 404 Not Found");
+    }
+
+    #[test]
+    fn positive_default_status_reason() {
+        assert_eq!(SearchResponse::new().status_reason(), Some("OK"));
+    }
+
+    fn receive_statuses(config: &Config) -> Vec<u16> {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let receiver = SSDPReceiver::with_filter(vec![recv_sock],
+                                                 Some(Duration::from_millis(200)),
+                                                 super::response_filter(config, None))
+            .unwrap();
+
+        send_sock.send_to(b"HTTP/1.1 404 Not Found\r\n\r\n", recv_addr).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        receiver.into_iter().map(|(response, _)| response.status_code()).collect()
+    }
+
+    #[test]
+    fn positive_arrival_order() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], Some(Duration::from_millis(200)))
+            .unwrap();
+
+        for code in 200..205 {
+            let raw_response = format!("HTTP/1.1 {} OK\r\n\r\n", code);
+            send_sock.send_to(raw_response.as_bytes(), recv_addr).unwrap();
+        }
+
+        let responses: Vec<SearchResponse> = receiver.into_iter().map(|(response, _)| response).collect();
+        let codes: Vec<u16> = responses.iter().map(|response| response.status_code()).collect();
+        let arrivals: Vec<Instant> = responses.iter().filter_map(|response| response.arrived_at()).collect();
+
+        assert_eq!(codes, vec![200, 201, 202, 203, 204]);
+        assert_eq!(arrivals.len(), codes.len());
+        assert!(arrivals.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(SearchResponse::new().arrived_at(), None);
+    }
+
+    #[test]
+    fn positive_expires_at() {
+        let info = PacketInfo {
+            src_addr: "192.168.1.2:1900".parse().unwrap(),
+            local_addr: "192.168.1.3:1900".parse().unwrap(),
+            if_index: None,
+            dst_addr: None,
+            arrived_at: Instant::now(),
+        };
+        let raw_response = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=1800\r\n\r\n";
+
+        let response = SearchResponse::raw_ssdp_with_info(raw_response.as_bytes(), &info).unwrap();
+
+        assert_eq!(response.max_age(), Some(1800));
+        assert_eq!(response.expires_at(), Some(info.arrived_at + Duration::from_secs(1800)));
+    }
+
+    #[test]
+    fn positive_expires_at_without_max_age() {
+        let raw_response = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: no-cache\r\n\r\n";
+
+        let response = SearchResponse::raw_ssdp(raw_response.as_bytes()).unwrap();
+
+        assert_eq!(response.max_age(), None);
+        assert_eq!(response.expires_at(), None);
+    }
+
+    #[test]
+    fn positive_unicast_response() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], Some(Duration::from_secs(2)))
+            .unwrap();
+
+        let mut response = SearchResponse::new();
+        response.set(ST::All);
+        response.unicast(recv_addr).unwrap();
+
+        let (received, src) = receiver.recv().unwrap();
+        assert_eq!(received.status_code(), 200);
+        assert_eq!(received.get::<ST>(), Some(&ST::All));
+        assert_ne!(src.port(), recv_addr.port());
+        assert_eq!(received.remote_addr(), Some(src));
+        assert_eq!(response.remote_addr(), None);
+    }
+
+    #[test]
+    fn positive_dedup_by_usn() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let first_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let second_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let receiver = SSDPReceiver::<SearchResponse>::new(vec![recv_sock], Some(Duration::from_millis(200)))
+            .unwrap();
+
+        let with_usn = |uuid: &str| format!("HTTP/1.1 200 OK\r\nUSN: uuid:{}\r\n\r\n", uuid);
+        first_sock.send_to(with_usn("a").as_bytes(), recv_addr).unwrap();
+        second_sock.send_to(with_usn("a").as_bytes(), recv_addr).unwrap();
+        second_sock.send_to(with_usn("b").as_bytes(), recv_addr).unwrap();
+        first_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+        first_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+        second_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        let unique: Vec<_> = receiver.dedup_by_usn()
+            .map(|(response, src)| (response.get::<USN>().cloned(), src))
+            .collect();
+
+        assert_eq!(unique,
+                   vec![(Some(USN(FieldMap::uuid("a"), None)), first_sock.local_addr().unwrap()),
+                        (Some(USN(FieldMap::uuid("b"), None)), second_sock.local_addr().unwrap()),
+                        (None, first_sock.local_addr().unwrap()),
+                        (None, second_sock.local_addr().unwrap())]);
+    }
+
+    #[test]
+    fn positive_lenient_keeps_error_status() {
+        assert_eq!(receive_statuses(&Config::new()), vec![404, 200]);
+    }
+
+    #[test]
+    fn positive_strict_drops_error_status() {
+        assert_eq!(receive_statuses(&Config::new().set_strict(true)), vec![200]);
+    }
+
+    /// Get the `ST` values of the given responses kept by the filter for a search for `target`.
+    fn matched_targets(config: &Config, target: ST) -> Vec<Option<ST>> {
+        let filter = super::response_filter(config, Some(&target));
+        let src_addr = "192.168.1.2:1900".parse().unwrap();
+
+        ["HTTP/1.1 200 OK\r\nST: upnp:rootdevice\r\n\r\n",
+         "HTTP/1.1 200 OK\r\nST: urn:schemas-upnp-org:device:MediaServer:1\r\n\r\n",
+         "HTTP/1.1 200 OK\r\n\r\n"]
+            .iter()
+            .map(|raw_response| SearchResponse::raw_ssdp(raw_response.as_bytes()).unwrap())
+            .filter(|response| filter(response, &src_addr))
+            .map(|response| response.get::<ST>().cloned())
+            .collect()
+    }
+
+    #[test]
+    fn positive_match_target_drops_other_targets() {
+        let root_device = ST::Target(FieldMap::upnp("rootdevice"));
+        let config = Config::new().set_match_target(true);
+
+        assert_eq!(matched_targets(&config, root_device.clone()), vec![Some(root_device), None]);
+    }
+
+    #[test]
+    fn positive_match_target_all() {
+        let config = Config::new().set_match_target(true);
+
+        assert_eq!(matched_targets(&config, ST::All).len(), 3);
+    }
+
+    #[test]
+    fn positive_match_target_disabled() {
+        let root_device = ST::Target(FieldMap::upnp("rootdevice"));
+
+        assert_eq!(matched_targets(&Config::new(), root_device).len(), 3);
+    }
+
+    /// Answer every search received on the socket until it times out.
+    fn reply_to_searches(sock: UdpSocket) {
+        sock.set_read_timeout(Some(Duration::from_secs(3))).unwrap();
+
+        thread::spawn(move || {
+            let mut buf = [0u8; 1500];
+            while let Ok((_, src)) = sock.recv_from(&mut buf) {
+                let _ = sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", src);
+            }
+        });
+    }
+
+    #[test]
+    #[ignore = "needs multicast on both an IPv4 and an IPv6 interface"]
+    fn positive_dual_stack_multicast() {
+        let addrs = message::map_selected(false, &InterfaceSet::new(), |&addr| Ok(Some(addr))).unwrap();
+        assert!(addrs.iter().any(|addr| addr.is_ipv4()));
+        assert!(addrs.iter().any(|addr| addr.is_ipv6()));
+
+        let config = Config::new().set_mode(IpVersionMode::Any);
+        let ipv4_sock = net::bind_reuse("0.0.0.0:0").unwrap();
+        let port = ipv4_sock.local_addr().unwrap().port();
+        let ipv6_sock = net::bind_reuse(("::", port)).unwrap();
+        let config = config.set_port(port);
+
+        let ipv4_mcast: IpAddr = config.ipv4_addr.parse().unwrap();
+        let ipv6_mcast: IpAddr = config.ipv6_addr.parse().unwrap();
+        for addr in &addrs {
+            if addr.is_ipv4() {
+                net::join_multicast(&ipv4_sock, addr, &ipv4_mcast).unwrap();
+            } else {
+                net::join_multicast(&ipv6_sock, addr, &ipv6_mcast).unwrap();
+            }
+        }
+        reply_to_searches(ipv4_sock);
+        reply_to_searches(ipv6_sock);
+
+        let mut request = SearchRequest::new();
+        request.set(Man);
+        request.set(MX(1));
+        request.set(ST::All);
+
+        let receiver = request.multicast_with_config(&config).unwrap();
+        let sources: Vec<_> = receiver.into_iter().map(|(_, src)| src).collect();
+
+        assert!(sources.iter().any(|src| src.is_ipv4()));
+        assert!(sources.iter().any(|src| src.is_ipv6()));
+    }
+
+    #[test]
+    fn positive_probe_responder() {
+        let responder = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let responder_addr = responder.local_addr().unwrap();
+        reply_to_searches(responder);
+
+        let mut request = SearchRequest::new();
+        request.set(Man);
+        request.set(ST::All);
+
+        let started = Instant::now();
+        let response = request.probe(responder_addr, Duration::from_secs(2)).unwrap();
+
+        assert_eq!(response.map(|response| response.status_code()), Some(200));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn positive_probe_timeout() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let mut request = SearchRequest::new();
+        request.set(Man);
+        request.set(ST::All);
+
+        let response = request.probe(silent.local_addr().unwrap(), Duration::from_millis(200)).unwrap();
+
+        assert!(response.is_none());
+    }
+
+    #[test]
+    #[ignore = "needs multicast on an IPv4 interface"]
+    fn positive_search_repeat() {
+        let ipv4_addrs: Vec<_> = message::map_selected(false, &InterfaceSet::new(), |&addr| Ok(Some(addr)))
+            .unwrap()
+            .into_iter()
+            .filter(|addr| addr.is_ipv4())
+            .collect();
+        assert!(!ipv4_addrs.is_empty());
+
+        let listen_sock = net::bind_reuse("0.0.0.0:0").unwrap();
+        let port = listen_sock.local_addr().unwrap().port();
+        let config = Config::new().set_mode(IpVersionMode::V4Only).set_port(port).set_search_repeat(3);
+
+        let mcast_ip: IpAddr = config.ipv4_addr.parse().unwrap();
+        for addr in &ipv4_addrs {
+            net::join_multicast(&listen_sock, addr, &mcast_ip).unwrap();
+        }
+        listen_sock.set_read_timeout(Some(Duration::from_millis(500))).unwrap();
+
+        let mut request = SearchRequest::new();
+        request.set(Man);
+        request.set(MX(1));
+        request.set(ST::All);
+        request.multicast_with_config(&config).unwrap();
+
+        let mut buf = [0u8; 1500];
+        let mut searches = 0;
+        while let Ok((len, _)) = listen_sock.recv_from(&mut buf) {
+            if SearchRequest::raw_ssdp(&buf[..len]).is_ok() {
+                searches += 1;
+            }
+        }
+
+        assert_eq!(searches, 3 * ipv4_addrs.len());
+    }
+
+    #[test]
+    #[ignore = "needs multicast on an IPv4 interface"]
+    fn positive_multicast_on() {
+        let local_ip = message::map_selected(false, &InterfaceSet::new(), |&addr| Ok(Some(addr)))
+            .unwrap()
+            .into_iter()
+            .find(|addr| addr.is_ipv4())
+            .unwrap()
+            .ip();
+
+        let listen_sock = net::bind_reuse("0.0.0.0:0").unwrap();
+        let port = listen_sock.local_addr().unwrap().port();
+        let config = Config::new().set_port(port);
+        net::join_multicast(&listen_sock, &SocketAddr::new(local_ip, 0), &config.ipv4_addr.parse().unwrap())
+            .unwrap();
+        reply_to_searches(listen_sock);
+
+        let mut request = SearchRequest::new();
+        request.set(Man);
+        request.set(MX(1));
+        request.set(ST::All);
+
+        let receiver = request.multicast_on_with_config(local_ip, &config).unwrap();
+        let locals: Vec<_> = receiver.into_iter()
+            .filter_map(|(response, _)| response.packet_info().map(|info| info.local_addr.ip()))
+            .collect();
+
+        assert!(!locals.is_empty());
+        assert!(locals.iter().all(|&ip| ip == local_ip));
+    }
+
+    #[test]
+    fn negative_multicast_on_unknown_interface() {
+        let mut request = SearchRequest::new();
+        request.set(Man);
+        request.set(MX(1));
+        request.set(ST::All);
+
+        match request.multicast_on("192.0.2.254".parse().unwrap()) {
+            Err(SSDPError(SSDPErrorKind::UnknownInterface(ip), _)) => {
+                assert_eq!(ip.to_string(), "192.0.2.254")
+            }
+            other => panic!("expected an unknown interface error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn positive_client_description() {
+        let raw_request = "M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nMAN: \"ssdp:discover\"\r\n\
+                           MX: 1\r\nST: ssdp:all\r\nUSER-AGENT: Linux/4.9 UPnP/2.0 Player/1.0\r\n\
+                           CPFN.UPNP.ORG: Living Room TV\r\n\r\n";
+
+        let request = SearchRequest::raw_ssdp(raw_request.as_bytes()).unwrap();
+
+        assert_eq!(request.client_description(),
+                   Some("Living Room TV (Linux/4.9 UPnP/2.0 Player/1.0)".to_owned()));
+    }
+
+    #[test]
+    fn positive_client_description_partial() {
+        let mut request = SearchRequest::new();
+        assert_eq!(request.client_description(), None);
+
+        request.set(UserAgent("Linux/4.9 UPnP/2.0 Player/1.0".to_owned()));
+        assert_eq!(request.client_description(), Some("Linux/4.9 UPnP/2.0 Player/1.0".to_owned()));
+    }
+
+    #[test]
+    fn positive_for_uuid() {
+        let uuid = "2fac1234-31f8-11b4-a222-08002b34c003";
+        let request = SearchRequest::for_uuid(uuid, 3).unwrap();
+
+        assert_eq!(request.get::<ST>(), Some(&ST::Target(FieldMap::uuid(uuid))));
+        assert_eq!(request.get::<MX>(), Some(&MX(3)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_for_uuid_malformed() {
+        SearchRequest::for_uuid("2fac1234-31f8-11b4-a222", 3).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_for_uuid_non_hex() {
+        SearchRequest::for_uuid("2fac1234-31f8-11b4-a222-08002b34c00g", 3).unwrap();
     }
 }
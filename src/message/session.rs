@@ -0,0 +1,369 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+
+use socket2::Socket;
+
+use error::SSDPResult;
+use header::{self, HeaderMut, HeaderRef, Location, NTS, USN};
+use message::{self, listen, Config};
+use message::notify::NotifyMessage;
+use message::search::{SearchRequest, SearchResponse};
+use receiver::{FromRawSSDP, SSDPReceiver};
+use net;
+
+/// A service seen on the network, identified by its `USN` header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Advertisement {
+    usn: USN,
+    location: Option<Location>,
+    src: SocketAddr,
+}
+
+impl Advertisement {
+    /// Get the unique service name of the service.
+    pub fn usn(&self) -> &USN {
+        &self.usn
+    }
+
+    /// Get the location of the description of the service, if it sent one.
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// Get the address the most recent message about the service came from.
+    pub fn src(&self) -> SocketAddr {
+        self.src
+    }
+}
+
+/// Enumerates changes to the set of services seen by a `DiscoverySession`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiscoveryEvent {
+    /// A service was seen for the first time.
+    Found(Advertisement),
+    /// A known service announced an update or moved to a new location.
+    Updated(Advertisement),
+    /// A known service said byebye or its max-age ran out.
+    Gone(USN),
+}
+
+/// Searches for services and then keeps track of them by listening for
+/// notifications, using the same sockets for both.
+///
+/// The sockets are bound to the configured port on all interfaces, so that
+/// both the unicast search responses and the multicast notifications reach
+/// them. If another process shares that port, the operating system may
+/// deliver some search responses to it instead.
+pub struct DiscoverySession {
+    receiver: SSDPReceiver<Incoming>,
+    tracker: Tracker,
+}
+
+impl DiscoverySession {
+    /// Multicast the search request and start tracking the services that
+    /// answer it or announce themselves afterwards.
+    pub fn start(request: &SearchRequest, config: &Config) -> SSDPResult<DiscoverySession> {
+        let sockets = listen::bind_multicast(config)?;
+        let local_addrs =
+            message::map_selected(config.send_global_ipv6, &config.interfaces, |&addr| Ok(Some(addr)))?;
+
+        for sock in &sockets {
+            let is_ipv4 = sock.local_addr()?.is_ipv4();
+
+            for local_addr in local_addrs.iter().filter(|addr| addr.is_ipv4() == is_ipv4) {
+                send_search(sock, local_addr, request, config)?;
+            }
+        }
+
+        Ok(DiscoverySession {
+            receiver: SSDPReceiver::new(sockets, None)?,
+            tracker: Tracker::new(),
+        })
+    }
+
+    /// Block until the next change to the set of known services.
+    ///
+    /// Returns `None` once the session can no longer receive messages.
+    pub fn next_event(&mut self) -> Option<DiscoveryEvent> {
+        loop {
+            let now = Instant::now();
+            if let Some(event) = self.tracker.expire(now) {
+                return Some(event);
+            }
+
+            let result = match self.tracker.next_expiry() {
+                Some(when) => self.receiver.recv_timeout(when.saturating_duration_since(now)),
+                None => self.receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match result {
+                Ok((incoming, src)) => {
+                    if let Some(event) = self.tracker.handle(incoming, src, Instant::now()) {
+                        return Some(event);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+}
+
+impl Iterator for DiscoverySession {
+    type Item = DiscoveryEvent;
+
+    fn next(&mut self) -> Option<DiscoveryEvent> {
+        self.next_event()
+    }
+}
+
+/// Multicast the search request out of the interface with the given local address.
+fn send_search(sock: &UdpSocket,
+               local_addr: &SocketAddr,
+               request: &SearchRequest,
+               config: &Config)
+               -> SSDPResult<()> {
+    let socket = Socket::from(sock.try_clone()?);
+    let ttl = config.ttl_for(&local_addr.ip());
+
+    let (mcast_addr, bytes) = match *local_addr {
+        SocketAddr::V4(n) => {
+            socket.set_multicast_if_v4(n.ip())?;
+            socket.set_multicast_ttl_v4(ttl)?;
+
+            let mcast_ip: IpAddr = config.ipv4_addr.parse()?;
+            (SocketAddr::new(mcast_ip, config.port), request.prepared_bytes(config)?)
+        }
+        SocketAddr::V6(n) => {
+            socket.set_multicast_if_v6(n.scope_id())?;
+            socket.set_multicast_hops_v6(ttl)?;
+
+            let mcast_addr = SocketAddr::V6(net::ipv6_group_addr(config.ipv6_addr.parse()?, config.port, &n));
+
+            // The default host header points at the IPv4 group
+            let mut request = request.clone();
+            request.set(header::host_for(&mcast_addr));
+
+            (mcast_addr, request.prepared_bytes(config)?)
+        }
+    };
+
+    debug!("Sending session search through {} to {}", local_addr, mcast_addr);
+    socket.send_to(&bytes, &mcast_addr.into())?;
+
+    Ok(())
+}
+
+/// Messages a `DiscoverySession` can learn about services from.
+enum Incoming {
+    Response(SearchResponse),
+    Notify(NotifyMessage),
+}
+
+impl FromRawSSDP for Incoming {
+    fn raw_ssdp(bytes: &[u8]) -> SSDPResult<Incoming> {
+        match SearchResponse::raw_ssdp(bytes) {
+            Ok(n) => Ok(Incoming::Response(n)),
+            Err(_) => NotifyMessage::raw_ssdp(bytes).map(Incoming::Notify),
+        }
+    }
+}
+
+/// Known services along with when they expire.
+struct Tracker {
+    known: HashMap<USN, (Advertisement, Option<Instant>)>,
+}
+
+impl Tracker {
+    fn new() -> Tracker {
+        Tracker { known: HashMap::new() }
+    }
+
+    /// Update the known services with the message, returning what changed.
+    fn handle(&mut self, incoming: Incoming, src: SocketAddr, now: Instant) -> Option<DiscoveryEvent> {
+        let (usn, nts, location, max_age) = match incoming {
+            Incoming::Response(ref n) => {
+                (n.get::<USN>(), Some(&NTS::Alive), n.get::<Location>(), n.max_age())
+            }
+            Incoming::Notify(ref n) => (n.get::<USN>(), n.get::<NTS>(), n.get::<Location>(), n.max_age()),
+        };
+
+        let (usn, nts) = match (usn, nts) {
+            (Some(usn), Some(nts)) => (usn.clone(), *nts),
+            _ => {
+                debug!("Ignoring message from {} without a USN or NTS", src);
+                return None;
+            }
+        };
+
+        if nts == NTS::ByeBye {
+            return self.known.remove(&usn).map(|_| DiscoveryEvent::Gone(usn));
+        }
+
+        let mut location = location.cloned();
+        let mut expires_at = max_age.map(|secs| now + Duration::from_secs(u64::from(secs)));
+
+        // Updates carry no CACHE-CONTROL and may leave out the LOCATION
+        if nts == NTS::Update {
+            if let Some(&(ref old, old_expires_at)) = self.known.get(&usn) {
+                location = location.or_else(|| old.location.clone());
+                expires_at = expires_at.or(old_expires_at);
+            }
+        }
+
+        let advertisement = Advertisement {
+            usn: usn.clone(),
+            location,
+            src,
+        };
+
+        match self.known.insert(usn, (advertisement.clone(), expires_at)) {
+            None => Some(DiscoveryEvent::Found(advertisement)),
+            Some((ref old, _)) if nts == NTS::Update || old.location != advertisement.location => {
+                Some(DiscoveryEvent::Updated(advertisement))
+            }
+            Some(_) => None,
+        }
+    }
+
+    /// Forget a service whose max-age has run out, if there is one.
+    fn expire(&mut self, now: Instant) -> Option<DiscoveryEvent> {
+        let expired = self.known
+            .iter()
+            .find(|&(_, &(_, expires_at))| expires_at.is_some_and(|when| when <= now))
+            .map(|(usn, _)| usn.clone());
+
+        expired.map(|usn| {
+            self.known.remove(&usn);
+            DiscoveryEvent::Gone(usn)
+        })
+    }
+
+    /// Get the earliest time at which a known service expires.
+    fn next_expiry(&self) -> Option<Instant> {
+        self.known.values().filter_map(|&(_, expires_at)| expires_at).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::time::{Duration, Instant};
+
+    use FieldMap;
+    use header::{HeaderMut, CacheControl, CacheDirective, Location, NT, NTS, USN};
+    use message::notify::NotifyMessage;
+    use message::search::SearchResponse;
+    use super::{DiscoveryEvent, Incoming, Tracker};
+
+    fn usn() -> USN {
+        USN(FieldMap::uuid("device"), Some(FieldMap::upnp("rootdevice")))
+    }
+
+    fn src() -> SocketAddr {
+        "192.168.1.2:1900".parse().unwrap()
+    }
+
+    fn alive(location: &str) -> Incoming {
+        let location = Location(location.to_owned());
+
+        Incoming::Notify(NotifyMessage::alive(NT(FieldMap::upnp("rootdevice")), usn(), location, 1800))
+    }
+
+    fn response(location: &str, max_age: u32) -> Incoming {
+        let mut response = SearchResponse::new();
+        response.set(usn());
+        response.set(Location(location.to_owned()));
+        response.set(CacheControl(vec![CacheDirective::MaxAge(max_age)]));
+
+        Incoming::Response(response)
+    }
+
+    fn is_found(event: Option<DiscoveryEvent>) -> bool {
+        matches!(event, Some(DiscoveryEvent::Found(_)))
+    }
+
+    #[test]
+    fn positive_found_then_quiet() {
+        let mut tracker = Tracker::new();
+        let now = Instant::now();
+
+        assert!(is_found(tracker.handle(response("http://192.168.1.2/root.xml", 1800), src(), now)));
+        assert_eq!(tracker.handle(alive("http://192.168.1.2/root.xml"), src(), now), None);
+    }
+
+    #[test]
+    fn positive_updated_on_new_location() {
+        let mut tracker = Tracker::new();
+        let now = Instant::now();
+
+        tracker.handle(alive("http://192.168.1.2/root.xml"), src(), now);
+
+        match tracker.handle(alive("http://192.168.1.3/root.xml"), src(), now) {
+            Some(DiscoveryEvent::Updated(ref n)) => {
+                assert_eq!(n.location(), Some(&Location("http://192.168.1.3/root.xml".to_owned())))
+            }
+            other => panic!("expected an update, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn positive_updated_on_ssdp_update() {
+        let mut tracker = Tracker::new();
+        let now = Instant::now();
+        tracker.handle(alive("http://192.168.1.2/root.xml"), src(), now);
+
+        let mut update = NotifyMessage::new();
+        update.set(usn());
+        update.set(NTS::Update);
+
+        assert!(matches!(tracker.handle(Incoming::Notify(update), src(), now),
+                         Some(DiscoveryEvent::Updated(_))));
+    }
+
+    #[test]
+    fn positive_gone_on_byebye() {
+        let mut tracker = Tracker::new();
+        let now = Instant::now();
+        let byebye = || Incoming::Notify(NotifyMessage::byebye(NT(FieldMap::upnp("rootdevice")), usn()));
+
+        assert_eq!(tracker.handle(byebye(), src(), now), None);
+
+        tracker.handle(alive("http://192.168.1.2/root.xml"), src(), now);
+        assert_eq!(tracker.handle(byebye(), src(), now), Some(DiscoveryEvent::Gone(usn())));
+    }
+
+    #[test]
+    fn positive_gone_on_expiry() {
+        let mut tracker = Tracker::new();
+        let now = Instant::now();
+
+        tracker.handle(response("http://192.168.1.2/root.xml", 10), src(), now);
+        assert_eq!(tracker.next_expiry(), Some(now + Duration::from_secs(10)));
+
+        assert_eq!(tracker.expire(now + Duration::from_secs(5)), None);
+        assert_eq!(tracker.expire(now + Duration::from_secs(10)), Some(DiscoveryEvent::Gone(usn())));
+        assert_eq!(tracker.next_expiry(), None);
+    }
+
+    #[test]
+    fn positive_update_keeps_expiry_and_location() {
+        let mut tracker = Tracker::new();
+        let now = Instant::now();
+        let location = Location("http://192.168.1.2/root.xml".to_owned());
+        let alive = NotifyMessage::alive(NT(FieldMap::upnp("rootdevice")), usn(), location.clone(), 10);
+        tracker.handle(Incoming::Notify(alive), src(), now);
+
+        let mut update = NotifyMessage::new();
+        update.set(usn());
+        update.set(NTS::Update);
+
+        match tracker.handle(Incoming::Notify(update), src(), now + Duration::from_secs(2)) {
+            Some(DiscoveryEvent::Updated(ref n)) => assert_eq!(n.location(), Some(&location)),
+            other => panic!("expected an update, got {:?}", other),
+        }
+        assert_eq!(tracker.next_expiry(), Some(now + Duration::from_secs(10)));
+    }
+}
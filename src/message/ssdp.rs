@@ -1,12 +1,12 @@
 use std::borrow::{Cow, ToOwned};
-use std::fmt::Debug;
+use std::fmt::{self, Debug, Display, Formatter};
 use std::io::Write;
-use std::net::{ToSocketAddrs, SocketAddr};
+use std::net::{ToSocketAddrs, SocketAddr, SocketAddrV6, UdpSocket};
 
 use hyper::Url;
 use hyper::buffer::BufReader;
 use hyper::client::request::Request;
-use hyper::header::{Headers, Header, HeaderFormat, ContentLength, Host};
+use hyper::header::{Headers, Header, HeaderFormat, CacheControl, ContentLength, Host, Server, UserAgent};
 use hyper::http::RawStatus;
 use hyper::http::h1::{self, Incoming};
 use hyper::method::Method;
@@ -16,14 +16,15 @@ use hyper::status::StatusCode;
 use hyper::uri::RequestUri;
 use hyper::version::HttpVersion;
 
-use {SSDPResult, SSDPErrorKind};
-use header::{HeaderRef, HeaderMut};
-use message::MessageType;
+use {ParseComponent, SSDPResult, SSDPErrorKind};
+use header::{self, HeaderRef, HeaderMut, MaxAge};
+use message::{self, Config, MessageType};
 use net;
+use net::connector::UdpConnector;
 use receiver::FromRawSSDP;
 
 
-/// Only Valid `SearchResponse` Code
+/// Status Given To Locally Constructed `SearchResponse` Messages
 const VALID_RESPONSE_CODE: u16 = 200;
 
 /// Appended To Destination Socket Addresses For URLs
@@ -38,29 +39,72 @@ const SEARCH_METHOD: &'static str = "M-SEARCH";
 pub struct SSDPMessage {
     method: MessageType,
     headers: Headers,
+    status: Option<RawStatus>,
+    raw: Option<Vec<u8>>,
 }
 
 impl SSDPMessage {
     /// Construct a new SSDPMessage.
     pub fn new(message_type: MessageType) -> SSDPMessage {
+        let status = match message_type {
+            MessageType::Response => Some(RawStatus(VALID_RESPONSE_CODE, Cow::Borrowed("OK"))),
+            _ => None,
+        };
+
         SSDPMessage {
             method: message_type,
             headers: Headers::new(),
+            status,
+            raw: None,
         }
     }
 
+    /// Get the exact bytes this message was received as.
+    ///
+    /// Returns `None` for messages that were constructed locally or had a
+    /// header changed since they were received, use `to_bytes()` for those.
+    pub fn raw_bytes(&self) -> Option<&[u8]> {
+        self.raw.as_ref().map(|raw| &raw[..])
+    }
+
+    /// Remember the bytes this message was parsed from.
+    fn with_raw(mut self, bytes: &[u8]) -> SSDPMessage {
+        self.raw = Some(bytes.to_vec());
+        self
+    }
+
     /// Get the type of this message.
     pub fn message_type(&self) -> MessageType {
         self.method
     }
 
+    /// Get the status code of this message, if it is a response.
+    pub fn status_code(&self) -> Option<u16> {
+        self.status.as_ref().map(|&RawStatus(code, _)| code)
+    }
+
+    /// Get the max-age directive of the cache control header, in seconds.
+    pub fn max_age(&self) -> Option<u32> {
+        HeaderRef::get::<MaxAge>(&self.headers).map(|&MaxAge(secs)| secs)
+    }
+
+    /// Get the reason phrase of this message, if it is a response.
+    pub fn status_reason(&self) -> Option<&str> {
+        self.status.as_ref().map(|RawStatus(_, reason)| &reason[..])
+    }
+
     /// Send this request to the given destination address using the given connector.
     ///
     /// The host header field will be taken care of by the underlying library.
+    ///
+    /// Messages with a line break in any header value are rejected, since that
+    /// would let the value inject headers of its own.
     pub fn send<A: ToSocketAddrs, C, S>(&self, connector: &mut C, dst_addr: A) -> SSDPResult<()>
         where C: NetworkConnector<Stream = S>,
               S: Into<Box<NetworkStream + Send>>
     {
+        self.check_headers()?;
+
         let dst_sock_addr = try!(net::addr_from_trait(dst_addr));
         match self.method {
             MessageType::Notify => {
@@ -78,13 +122,152 @@ impl SSDPMessage {
                 let dst_port = dst_sock_addr.port();
 
                 let net_stream = try!(connector.connect(&dst_ip_string[..], dst_port, "")).into();
+                let status = StatusCode::from_u16(self.status_code().unwrap_or(VALID_RESPONSE_CODE));
 
-                send_response(&self.headers, net_stream)
+                send_response(&self.headers, status, net_stream)
+            }
+        }
+    }
+
+    /// Send this message to the given destination address over an existing
+    /// socket, after preparing it under the config.
+    pub fn send_from<A: ToSocketAddrs>(&self,
+                                       sock: &UdpSocket,
+                                       dst_addr: A,
+                                       config: &Config)
+                                       -> SSDPResult<()> {
+        let mut connector = UdpConnector::from_socket(sock.try_clone()?);
+
+        self.prepare(config)?.send(&mut connector, dst_addr)
+    }
+
+    /// Serialize this message into the bytes of a single SSDP datagram.
+    ///
+    /// Requests without a host header will be given one pointing at the
+    /// standard IPv4 multicast address. Header values are not validated, use
+    /// `check_headers()` before sending the bytes anywhere.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut headers = Headers::new();
+        if self.method != MessageType::Response && self.headers.get::<Host>().is_none() {
+            headers.set(Host {
+                hostname: message::UPNP_MULTICAST_IPV4_ADDR.to_owned(),
+                port: Some(message::UPNP_MULTICAST_PORT),
+            });
+        }
+        copy_headers(&self.headers, &mut headers);
+        headers.set(ContentLength(0));
+
+        format!("{}\r\n{}\r\n", self.start_line(), headers).into_bytes()
+    }
+
+    /// Get a copy of this message that is ready to be sent under the config.
+    ///
+    /// Searches without a `USER-AGENT` header and other messages without a
+    /// `SERVER` header are given one built from the configured server info.
+    /// The size of the message is then checked with `check_size()`.
+    pub fn prepare(&self, config: &Config) -> SSDPResult<SSDPMessage> {
+        let mut message = self.clone();
+        let product = config.server_info().to_value()?;
+
+        match self.method {
+            MessageType::Search if self.headers.get::<UserAgent>().is_none() => {
+                message.set(UserAgent(product))
+            }
+            MessageType::Notify | MessageType::Response if self.headers.get::<Server>().is_none() => {
+                message.set(Server(product))
+            }
+            _ => (),
+        }
+
+        message.check_size(config.check_size)?;
+
+        Ok(message)
+    }
+
+    /// Check that no header name or value contains a CR or LF.
+    ///
+    /// Hyper would replace line breaks in values with spaces, silently changing
+    /// them, so the raw values are checked instead.
+    pub fn check_headers(&self) -> SSDPResult<()> {
+        let is_line_break = |byte: &u8| *byte == b'\r' || *byte == b'\n';
+
+        for view in self.headers.iter() {
+            let name = view.name();
+            let values = self.headers.get_raw(name).unwrap_or(&[]);
+
+            if name.bytes().any(|byte| is_line_break(&byte)) ||
+               values.iter().any(|value| value.iter().any(&is_line_break)) {
+                return Err(SSDPErrorKind::HeaderInjection(name.to_owned()).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that this message fits within `MAX_SSDP_MESSAGE_SIZE`.
+    ///
+    /// Oversized messages are logged, or rejected if `reject` is set.
+    pub fn check_size(&self, reject: bool) -> SSDPResult<()> {
+        let size = self.to_bytes().len();
+
+        if size <= message::MAX_SSDP_MESSAGE_SIZE {
+            Ok(())
+        } else if reject {
+            Err(SSDPErrorKind::MessageTooLarge(size).into())
+        } else {
+            warn!("Sending {} byte message, larger than the {} bytes that safely fit in a datagram",
+                  size,
+                  message::MAX_SSDP_MESSAGE_SIZE);
+            Ok(())
+        }
+    }
+
+    /// Get the request or status line of this message, such as `NOTIFY * HTTP/1.1`
+    /// or `HTTP/1.1 200 OK`.
+    ///
+    /// Received messages only parse if their start line is valid, so this is
+    /// the line they were sent with.
+    pub fn start_line(&self) -> String {
+        match self.method {
+            MessageType::Notify => format!("{} * HTTP/1.1", NOTIFY_METHOD),
+            MessageType::Search => format!("{} * HTTP/1.1", SEARCH_METHOD),
+            MessageType::Response => {
+                match self.status {
+                    Some(RawStatus(code, ref reason)) => format!("HTTP/1.1 {} {}", code, reason),
+                    None => format!("HTTP/1.1 {}", StatusCode::from_u16(VALID_RESPONSE_CODE)),
+                }
             }
         }
     }
 }
 
+/// Renders the message for humans, one indented header per line.
+///
+/// Headers are sorted by name and annotated with their parsed values where
+/// that helps, so the output is not the same as what is sent on the wire.
+impl Display for SSDPMessage {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.start_line())?;
+
+        let mut views: Vec<_> = self.headers.iter().collect();
+        views.sort_by_key(|view| view.name().to_ascii_uppercase());
+
+        for view in views {
+            write!(f, "  {}: {}", view.name().to_ascii_uppercase(), view.value_string())?;
+
+            if view.is::<CacheControl>() {
+                if let Some(n) = self.max_age() {
+                    write!(f, " (max-age {} seconds)", n)?;
+                }
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[allow(unused)]
 /// Send a request using the connector with the supplied method and headers.
 fn send_request<C, S>(method: &str,
@@ -101,6 +284,10 @@ fn send_request<C, S>(method: &str,
 
     let mut request = try!(Request::with_connector(Method::Extension(method.to_owned()), url, connector));
 
+    // Link-local addresses need the zone of the outgoing interface, which can
+    // not be part of the url itself.
+    request.headers_mut().set(header::host_for(&dst_addr));
+
     trace!("Copying headers...");
     copy_headers(headers, request.headers_mut());
     trace!("Setting length");
@@ -115,8 +302,8 @@ fn send_request<C, S>(method: &str,
     Ok(())
 }
 
-/// Send an Ok response on the Writer with the supplied headers.
-fn send_response<W>(headers: &Headers, mut dst_writer: W) -> SSDPResult<()>
+/// Send a response on the Writer with the supplied status and headers.
+fn send_response<W>(headers: &Headers, status: StatusCode, mut dst_writer: W) -> SSDPResult<()>
     where W: Write
 {
     let mut temp_headers = Headers::new();
@@ -125,7 +312,7 @@ fn send_response<W>(headers: &Headers, mut dst_writer: W) -> SSDPResult<()>
     temp_headers.set(ContentLength(0));
 
     let mut response = Response::new(&mut dst_writer as &mut Write, &mut temp_headers);
-    *response.status_mut() = StatusCode::Ok;
+    *response.status_mut() = status;
 
     // Have to make sure response is destroyed here for lifetime issues with temp_headers
     try!(try!(response.start()).end());
@@ -134,7 +321,14 @@ fn send_response<W>(headers: &Headers, mut dst_writer: W) -> SSDPResult<()>
 }
 
 /// Convert the given address to a Url with a base of "udp://".
+///
+/// The scope id of IPv6 addresses is dropped, the connector sends out of the
+/// interface it is bound to.
 fn url_from_addr(addr: SocketAddr) -> SSDPResult<Url> {
+    let addr = match addr {
+        SocketAddr::V6(n) => SocketAddr::V6(SocketAddrV6::new(*n.ip(), n.port(), n.flowinfo(), 0)),
+        n => n,
+    };
     let str_url = BASE_HOST_URL.chars()
         .chain(addr.to_string()[..].chars())
         .collect::<String>();
@@ -171,12 +365,14 @@ impl HeaderMut for SSDPMessage {
     fn set<H>(&mut self, value: H)
         where H: Header + HeaderFormat
     {
+        self.raw = None;
         HeaderMut::set(&mut self.headers, value)
     }
 
     fn set_raw<K>(&mut self, name: K, value: Vec<Vec<u8>>)
         where K: Into<Cow<'static, str>> + Debug
     {
+        self.raw = None;
         HeaderMut::set_raw(&mut self.headers, name, value)
     }
 }
@@ -186,14 +382,14 @@ impl FromRawSSDP for SSDPMessage {
         let mut buf_reader = BufReader::new(bytes);
 
         if let Ok(parts) = h1::parse_request(&mut buf_reader) {
-            let message_result = message_from_request(parts);
+            let message_result = message_from_request(parts).map(|message| message.with_raw(bytes));
 
             log_message_result(&message_result, bytes);
             message_result
         } else {
             match h1::parse_response(&mut buf_reader) {
                 Ok(parts) => {
-                    let message_result = message_from_response(parts);
+                    let message_result = message_from_response(parts).map(|message| message.with_raw(bytes));
 
                     log_message_result(&message_result, bytes);
                     message_result
@@ -201,13 +397,112 @@ impl FromRawSSDP for SSDPMessage {
                 Err(err) => {
                     debug!("Failed parsing http response: {}, data: {}", err, String::from_utf8_lossy(bytes));
 
-                    Err(SSDPErrorKind::InvalidHttp(bytes.to_owned()).into())
+                    match locate_parse_error(bytes) {
+                        Some((component, offset)) => Err(SSDPErrorKind::Parse(component, offset).into()),
+                        None => Err(SSDPErrorKind::InvalidHttp(bytes.to_owned()).into()),
+                    }
                 }
             }
         } 
     }
 }
 
+/// Classify a datagram by its start line, without parsing the headers.
+///
+/// This lets a listener route mixed traffic before paying for a full parse.
+/// The full parse may still reject a datagram this accepts.
+pub fn classify(bytes: &[u8]) -> SSDPResult<MessageType> {
+    let line = match Lines::new(bytes).next() {
+        Some((_, line)) => line,
+        None => return Err(SSDPErrorKind::Parse(ParseComponent::StartLine, 0).into()),
+    };
+    let parts: Vec<&[u8]> = line.split(|&b| b == b' ').collect();
+
+    match parts[..] {
+        [method, b"*", b"HTTP/1.1"] if method == NOTIFY_METHOD.as_bytes() => Ok(MessageType::Notify),
+        [method, b"*", b"HTTP/1.1"] if method == SEARCH_METHOD.as_bytes() => Ok(MessageType::Search),
+        [b"HTTP/1.1", code, ..] if code.len() == 3 && code.iter().all(u8::is_ascii_digit) => {
+            Ok(MessageType::Response)
+        }
+        _ => Err(SSDPErrorKind::Parse(ParseComponent::StartLine, 0).into()),
+    }
+}
+
+/// Find the component of a message that failed to parse as HTTP, along with
+/// the byte offset that component starts at.
+///
+/// Returns None if no malformed component could be found.
+fn locate_parse_error(bytes: &[u8]) -> Option<(ParseComponent, usize)> {
+    let mut lines = Lines::new(bytes);
+
+    match lines.next() {
+        Some((_, line)) if is_start_line(line) => (),
+        _ => return Some((ParseComponent::StartLine, 0)),
+    }
+
+    for (offset, line) in &mut lines {
+        if line.is_empty() {
+            return None;
+        } else if !is_header_line(line) {
+            return Some((ParseComponent::Header, offset));
+        }
+    }
+
+    Some((ParseComponent::Terminator, bytes.len()))
+}
+
+/// Check if the line looks like a request line or a status line.
+fn is_start_line(line: &[u8]) -> bool {
+    let parts: Vec<&[u8]> = line.split(|&b| b == b' ').collect();
+
+    if line.starts_with(b"HTTP/") {
+        parts.len() >= 2 && parts[1].len() == 3 && parts[1].iter().all(u8::is_ascii_digit)
+    } else {
+        parts.len() == 3 && !parts[0].is_empty() && !parts[1].is_empty() && parts[2].starts_with(b"HTTP/")
+    }
+}
+
+/// Check if the line looks like a header line.
+fn is_header_line(line: &[u8]) -> bool {
+    match line.iter().position(|&b| b == b':') {
+        Some(n) => n > 0 && line[..n].iter().all(|&b| b.is_ascii_graphic()),
+        None => false,
+    }
+}
+
+/// Iterator over the lines of a message and the byte offsets they start at.
+///
+/// Lines may be terminated by either CRLF or LF, the final line is only yielded
+/// if it is terminated.
+struct Lines<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn new(bytes: &'a [u8]) -> Lines<'a> {
+        Lines { bytes, offset: 0 }
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = (usize, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.bytes[self.offset..];
+        let end = rest.iter().position(|&b| b == b'\n')?;
+
+        let start = self.offset;
+        self.offset += end + 1;
+
+        let line = &rest[..end];
+        match line.last() {
+            Some(&b'\r') => Some((start, &line[..end - 1])),
+            _ => Some((start, line)),
+        }
+    }
+}
+
 /// Logs a debug! message based on the value of the `SSDPResult`.
 fn log_message_result(result: &SSDPResult<SSDPMessage>, message: &[u8]) {
     match *result {
@@ -230,12 +525,16 @@ fn message_from_request(parts: Incoming<(Method, RequestUri)>) -> SSDPResult<SSD
                     Ok(SSDPMessage {
                         method: MessageType::Notify,
                         headers: headers,
+                        status: None,
+                        raw: None,
                     })
                 }
                 SEARCH_METHOD => {
                     Ok(SSDPMessage {
                         method: MessageType::Search,
                         headers: headers,
+                        status: None,
+                        raw: None,
                     })
                 }
                 _ => Err(SSDPErrorKind::InvalidMethod(n).into()),
@@ -249,16 +548,19 @@ fn message_from_request(parts: Incoming<(Method, RequestUri)>) -> SSDPResult<SSD
 }
 
 /// Attempts to construct an `SSDPMessage` from the given response pieces.
+///
+/// Responses with a non-200 status code are accepted, it is up to the caller to
+/// reject them if they so choose.
 fn message_from_response(parts: Incoming<RawStatus>) -> SSDPResult<SSDPMessage> {
-    let RawStatus(status_code, _) = parts.subject;
     let headers = parts.headers;
 
     try!(validate_http_version(parts.version));
-    try!(validate_response_code(status_code));
 
     Ok(SSDPMessage {
         method: MessageType::Response,
         headers: headers,
+        status: Some(parts.subject),
+        raw: None,
     })
 }
 
@@ -284,15 +586,6 @@ fn validate_http_host<T>(headers: T) -> SSDPResult<()>
     }
 }
 
-/// Validate the response code for an SSDP message.
-fn validate_response_code(code: u16) -> SSDPResult<()> {
-    if code != VALID_RESPONSE_CODE {
-        Err(SSDPErrorKind::ResponseCode(code).into())
-    } else {
-        Ok(())
-    }
-}
-
 #[cfg(test)]
 mod mocks {
     use std::cell::RefCell;
@@ -377,10 +670,13 @@ mod mocks {
 #[cfg(test)]
 mod tests {
     mod send {
+        use std::net::SocketAddrV6;
         use std::sync::mpsc::Receiver;
 
         use super::super::mocks::MockConnector;
         use super::super::SSDPMessage;
+        use SSDPErrorKind;
+        use header::HeaderMut;
         use message::MessageType;
 
         fn join_buffers(recv_list: &[Receiver<Vec<u8>>]) -> Vec<u8> {
@@ -442,13 +738,209 @@ mod tests {
 
             assert!(sent_message.contains("Host: 127.0.0.1:0"));
         }
+
+        #[test]
+        fn positive_scoped_host_header() {
+            let message = SSDPMessage::new(MessageType::Search);
+            let mut connector = MockConnector::new();
+            let dst_addr = SocketAddrV6::new("ff02::c".parse().unwrap(), 1900, 0, 3);
+
+            message.send(&mut connector, dst_addr).unwrap();
+
+            let sent_message = String::from_utf8(join_buffers(&*connector.receivers.borrow())).unwrap();
+
+            assert!(sent_message.contains("Host: [ff02::c%3]:1900"));
+        }
+
+        #[test]
+        fn negative_header_injection() {
+            let mut message = SSDPMessage::new(MessageType::Response);
+            message.set_raw("Location", vec![b"http://192.168.1.2/\r\nX-Injected: true".to_vec()]);
+            let mut connector = MockConnector::new();
+
+            let result = message.send(&mut connector, ("127.0.0.1", 0));
+
+            match result.map_err(|err| err.0) {
+                Err(SSDPErrorKind::HeaderInjection(ref name)) => assert_eq!(name, "Location"),
+                other => panic!("expected header injection error, got {:?}", other),
+            }
+            assert!(join_buffers(&*connector.receivers.borrow()).is_empty());
+        }
+
+        #[test]
+        fn negative_header_injection_line_feed() {
+            let mut message = SSDPMessage::new(MessageType::Search);
+            message.set_raw("ST", vec![b"ssdp:all\nX-Injected: true".to_vec()]);
+
+            assert!(message.check_headers().is_err());
+        }
+
+        #[test]
+        fn negative_header_injection_name() {
+            let mut message = SSDPMessage::new(MessageType::Search);
+            message.set_raw("X-Name\r\nX-Injected", vec![b"true".to_vec()]);
+
+            assert!(message.check_headers().is_err());
+        }
+
+        #[test]
+        fn positive_check_headers() {
+            let mut message = SSDPMessage::new(MessageType::Search);
+            message.set_raw("ST", vec![b"ssdp:all".to_vec()]);
+
+            message.check_headers().unwrap();
+        }
     }
 
-    mod parse {
+    mod display {
+        use super::super::SSDPMessage;
+        use header::{HeaderMut, CacheControl, CacheDirective, MX};
+        use message::MessageType;
+        use receiver::FromRawSSDP;
+
+        #[test]
+        fn positive_start_line() {
+            let message = SSDPMessage::new(MessageType::Response);
+
+            assert_eq!(message.to_string().lines().next(), Some("HTTP/1.1 200 OK"));
+        }
+
+        #[test]
+        fn positive_received_start_line() {
+            let message = SSDPMessage::raw_ssdp(b"HTTP/1.1 404 Not Found\r\n\r\n").unwrap();
+
+            assert_eq!(message.start_line(), "HTTP/1.1 404 Not Found");
+            assert_eq!(message.status_code(), Some(404));
+        }
+
+        #[test]
+        fn positive_sorted_headers() {
+            let mut message = SSDPMessage::new(MessageType::Search);
+            message.set(MX(5));
+            message.set_raw("Host", vec![b"239.255.255.250:1900".to_vec()]);
+
+            let lines: Vec<String> = message.to_string().lines().map(|line| line.to_owned()).collect();
+
+            assert_eq!(lines, vec!["M-SEARCH * HTTP/1.1", "  HOST: 239.255.255.250:1900", "  MX: 5"]);
+        }
+
+        #[test]
+        fn positive_max_age_annotation() {
+            let mut message = SSDPMessage::new(MessageType::Notify);
+            message.set(CacheControl(vec![CacheDirective::MaxAge(1800)]));
+
+            assert!(message.to_string().contains("CACHE-CONTROL: max-age=1800 (max-age 1800 seconds)"));
+        }
+    }
+
+    mod prepare {
+        use hyper::header::{Server, UserAgent};
+
+        use super::super::SSDPMessage;
+        use header::{HeaderMut, HeaderRef, ServerInfo};
+        use message::{Config, MessageType, DEFAULT_PRODUCT_TOKEN};
+
+        #[test]
+        fn positive_search_user_agent() {
+            let config = Config::new().set_product_token("MyApp/1.0");
+            let message = SSDPMessage::new(MessageType::Search).prepare(&config).unwrap();
+
+            let UserAgent(ref agent) = *message.get::<UserAgent>().unwrap();
+            assert!(agent.ends_with(" UPnP/1.1 MyApp/1.0"));
+            assert_eq!(agent.split(' ').count(), 3);
+            assert!(message.get::<Server>().is_none());
+        }
+
+        #[test]
+        fn positive_response_default_server() {
+            let message = SSDPMessage::new(MessageType::Response).prepare(&Config::new()).unwrap();
+
+            let Server(ref server) = *message.get::<Server>().unwrap();
+            assert!(server.ends_with(DEFAULT_PRODUCT_TOKEN));
+        }
+
+        #[test]
+        fn positive_existing_server_kept() {
+            let mut message = SSDPMessage::new(MessageType::Notify);
+            message.set(Server("Linux/4.0 UPnP/1.0 Other/2.0".to_owned()));
+
+            let prepared = message.prepare(&Config::new().set_product_token("MyApp/1.0")).unwrap();
+            assert_eq!(prepared.get::<Server>(), Some(&Server("Linux/4.0 UPnP/1.0 Other/2.0".to_owned())));
+        }
+
+        #[test]
+        fn positive_configured_server_info() {
+            let info = ServerInfo::new("MyApp/1.0").os("Linux", "4.9").upnp_version("2.0");
+            let config = Config::new().set_product_token("Other/1.0").set_server_info(info);
+
+            let message = SSDPMessage::new(MessageType::Notify).prepare(&config).unwrap();
+
+            assert_eq!(message.get::<Server>(), Some(&Server("Linux/4.9 UPnP/2.0 MyApp/1.0".to_owned())));
+        }
+
+        #[test]
+        #[should_panic]
+        fn negative_product_token_line_break() {
+            let config = Config::new().set_product_token("MyApp/1.0\r\nX-Injected: true");
+
+            SSDPMessage::new(MessageType::Search).prepare(&config).unwrap();
+        }
+    }
+
+    mod size {
         use super::super::SSDPMessage;
+        use header::HeaderMut;
+        use message::{self, Config, MessageType};
+
+        fn oversized_message() -> SSDPMessage {
+            let mut message = SSDPMessage::new(MessageType::Notify);
+            message.set_raw("X-Padding", vec![vec![b'a'; message::MAX_SSDP_MESSAGE_SIZE]]);
+
+            message
+        }
+
+        #[test]
+        fn positive_small_message_rejecting() {
+            assert!(SSDPMessage::new(MessageType::Notify).check_size(true).is_ok());
+        }
+
+        #[test]
+        fn positive_oversized_message_lenient() {
+            assert!(oversized_message().check_size(false).is_ok());
+        }
+
+        #[test]
+        #[should_panic]
+        fn negative_oversized_message_rejecting() {
+            oversized_message().check_size(true).unwrap();
+        }
+
+        #[test]
+        fn positive_oversized_message_strict_prepared() {
+            assert!(oversized_message().prepare(&Config::new().set_strict(true)).is_ok());
+        }
+
+        #[test]
+        #[should_panic]
+        fn negative_oversized_message_check_size_prepared() {
+            oversized_message().prepare(&Config::new().set_check_size(true)).unwrap();
+        }
+    }
+
+    mod parse {
+        use super::super::{classify, SSDPMessage};
+        use {ParseComponent, SSDPErrorKind};
         use header::HeaderRef;
+        use message::MessageType;
         use receiver::FromRawSSDP;
 
+        fn parse_error(raw_message: &str) -> (ParseComponent, usize) {
+            match SSDPMessage::raw_ssdp(raw_message.as_bytes()).unwrap_err().kind() {
+                &SSDPErrorKind::Parse(component, offset) => (component, offset),
+                other => panic!("Expected A Parse Error, Found {:?}", other),
+            }
+        }
+
         #[test]
         fn positive_valid_http() {
             let raw_message = "NOTIFY * HTTP/1.1\r\nHOST: 192.168.1.1\r\n\r\n";
@@ -487,5 +979,54 @@ mod tests {
 
             SSDPMessage::raw_ssdp(raw_message.as_bytes()).unwrap();
         }
+
+        #[test]
+        fn negative_start_line_offset() {
+            let raw_message = "GARBAGE\r\nHOST: 192.168.1.1\r\n\r\n";
+
+            assert_eq!(parse_error(raw_message), (ParseComponent::StartLine, 0));
+        }
+
+        #[test]
+        fn negative_header_offset() {
+            let raw_message = "NOTIFY * HTTP/1.1\r\nHOST: 192.168.1.1\r\nNO COLON\r\n\r\n";
+
+            assert_eq!(parse_error(raw_message), (ParseComponent::Header, 38));
+        }
+
+        #[test]
+        fn negative_response_header_offset() {
+            let raw_message = "HTTP/1.1 200 OK\r\n: no name\r\n\r\n";
+
+            assert_eq!(parse_error(raw_message), (ParseComponent::Header, 17));
+        }
+
+        #[test]
+        fn negative_terminator_offset() {
+            let raw_message = "NOTIFY * HTTP/1.1\r\nHOST: 192.168.1.1\r\n";
+
+            assert_eq!(parse_error(raw_message), (ParseComponent::Terminator, raw_message.len()));
+        }
+
+        #[test]
+        fn positive_classify() {
+            assert_eq!(classify(b"NOTIFY * HTTP/1.1\r\nNO COLON\r\n").unwrap(), MessageType::Notify);
+            assert_eq!(classify(b"M-SEARCH * HTTP/1.1\r\n\r\n").unwrap(), MessageType::Search);
+            assert_eq!(classify(b"HTTP/1.1 200 OK\r\n").unwrap(), MessageType::Response);
+            assert_eq!(classify(b"HTTP/1.1 404\n").unwrap(), MessageType::Response);
+        }
+
+        #[test]
+        fn negative_classify_unknown_start_line() {
+            let raw_messages =
+                ["NOTIFY / HTTP/1.1\r\n", "GET * HTTP/1.1\r\n", "HTTP/1.1 OK\r\n", "NOTIFY * HTTP/1.1"];
+
+            for raw_message in &raw_messages {
+                match classify(raw_message.as_bytes()).unwrap_err().kind() {
+                    &SSDPErrorKind::Parse(ParseComponent::StartLine, 0) => (),
+                    other => panic!("Expected A Start Line Parse Error, Found {:?}", other),
+                }
+            }
+        }
     }
 }
@@ -4,7 +4,9 @@ use std::str::FromStr;
 
 use hyper::error;
 use hyper::net::NetworkConnector;
+use socket2::Socket;
 
+use message::UPNP_MULTICAST_PORT;
 use net::sender::UdpSender;
 use net;
 
@@ -14,19 +16,62 @@ pub struct UdpConnector(UdpSocket);
 
 impl UdpConnector {
     /// Create a new UdpConnector that will be bound to the given local address.
-    pub fn new<A: ToSocketAddrs>(local_addr: A, _: Option<u32>) -> io::Result<UdpConnector> {
-        let addr = try!(net::addr_from_trait(local_addr));
+    ///
+    /// The multicast ttl is applied as the TTL for IPv4 sockets and as the hop
+    /// limit for IPv6 sockets.
+    pub fn new<A: ToSocketAddrs>(local_addr: A, multicast_ttl: Option<u32>) -> io::Result<UdpConnector> {
+        UdpConnector::with_bind_retries(local_addr, multicast_ttl, net::DEFAULT_BIND_RETRIES)
+    }
+
+    /// Create a new UdpConnector that sends over an existing socket.
+    ///
+    /// The socket is used as is, options such as the multicast TTL are left
+    /// as they were configured by the caller.
+    pub fn from_socket(udp: UdpSocket) -> UdpConnector {
+        UdpConnector(udp)
+    }
+
+    /// Create a new UdpConnector as with `UdpConnector::new`, retrying the bind
+    /// up to `bind_retries` times while the local address is still in use.
+    pub fn with_bind_retries<A: ToSocketAddrs>(local_addr: A,
+                                               multicast_ttl: Option<u32>,
+                                               bind_retries: u32)
+                                               -> io::Result<UdpConnector> {
+        let addr = net::addr_from_trait(local_addr)?;
         debug!("Attempting to connect to {}", addr);
 
-        let udp = try!(UdpSocket::bind(addr));
+        // Other SSDP software is likely bound to the standard port as well
+        let udp = bind(addr, bind_retries, addr.port() == UPNP_MULTICAST_PORT)?;
+
+        let socket = Socket::from(udp);
+
+        // Groups beyond the link scope are not sent through the interface
+        // named by the scope id of the destination, so pin the interface here
+        if let SocketAddr::V6(n) = addr {
+            if n.scope_id() != 0 {
+                socket.set_multicast_if_v6(n.scope_id())?;
+            }
+        }
 
-        // TODO: This throws an invalid argument error
-        // if let Some(n) = multicast_ttl {
-        //     trace!("Setting ttl to {}", n);
-        //     try!(udp.set_multicast_ttl_v4(n));
-        // }
+        if let Some(n) = multicast_ttl {
+            trace!("Setting ttl to {}", n);
 
-        Ok(UdpConnector(udp))
+            match addr {
+                SocketAddr::V4(_) => socket.set_multicast_ttl_v4(n)?,
+                SocketAddr::V6(_) => socket.set_multicast_hops_v6(n)?,
+            }
+        }
+
+        Ok(UdpConnector(socket.into_udp_socket()))
+    }
+
+    /// Set whether multicast sent from this connector is looped back to the
+    /// local host, using `IP_MULTICAST_LOOP` or `IPV6_MULTICAST_LOOP`.
+    pub fn set_multicast_loop(&self, on: bool) -> io::Result<()> {
+        match self.local_addr()? {
+            SocketAddr::V4(_) => self.0.set_multicast_loop_v4(on),
+            SocketAddr::V6(_) => self.0.set_multicast_loop_v6(on),
+        }
     }
 
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
@@ -69,3 +114,41 @@ impl NetworkConnector for UdpConnector {
         Ok(UdpSender::new(udp_sock, sock_addr))
     }
 }
+
+/// Bind a socket to the local address, shared with other sockets if `reuse` is set.
+fn bind(addr: SocketAddr, bind_retries: u32, reuse: bool) -> io::Result<UdpSocket> {
+    if reuse {
+        net::retry_bind(bind_retries, || net::bind_reuse(addr))
+    } else {
+        net::retry_bind(bind_retries, || UdpSocket::bind(addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UdpConnector;
+
+    #[test]
+    fn positive_multicast_ttl_v4() {
+        let connector = UdpConnector::new(("127.0.0.1", 0), Some(4)).unwrap();
+
+        assert_eq!(connector.deconstruct().multicast_ttl_v4().unwrap(), 4);
+    }
+
+    #[test]
+    fn positive_shared_multicast_port() {
+        let first = super::bind("127.0.0.1:0".parse().unwrap(), 0, true).unwrap();
+        let addr = first.local_addr().unwrap();
+        let second = super::bind(addr, 0, true).unwrap();
+
+        assert_eq!(second.local_addr().unwrap(), addr);
+    }
+
+    #[test]
+    fn positive_multicast_loop_v4() {
+        let connector = UdpConnector::new(("127.0.0.1", 0), None).unwrap();
+        connector.set_multicast_loop(false).unwrap();
+
+        assert!(!connector.deconstruct().multicast_loop_v4().unwrap());
+    }
+}
@@ -3,17 +3,26 @@
 //! This module deals with primitives for working with external libraries to write
 //! data to UDP sockets as a stream, and read data from UDP sockets as packets.
 
+#[cfg(target_os = "linux")]
+use std::ffi::CString;
 use std::io::{self, ErrorKind};
-use std::net::{IpAddr, SocketAddr};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6};
 use std::net::{ToSocketAddrs, UdpSocket};
+use std::thread;
+use std::time::Duration;
 
-#[cfg(not(windows))]
 use socket2::{Domain, Protocol, SockAddr, Socket, Type};
 
 pub mod connector;
 pub mod packet;
 pub mod sender;
 
+/// Default number of times to retry a bind whose address is still in use.
+pub const DEFAULT_BIND_RETRIES: u32 = 3;
+
+/// Delay before the first bind retry, doubled on every following retry.
+const BIND_RETRY_BACKOFF_MILLIS: u64 = 50;
+
 #[derive(Copy, Clone)]
 pub enum IpVersionMode {
     V4Only,
@@ -28,6 +37,30 @@ impl IpVersionMode {
             SocketAddr::V6(_) => Ok(IpVersionMode::V6Only),
         }
     }
+
+    /// Check if the given address is of a version allowed by this mode.
+    pub fn allows(&self, addr: &SocketAddr) -> bool {
+        matches!((*self, *addr),
+                 (IpVersionMode::Any, _) |
+                 (IpVersionMode::V4Only, SocketAddr::V4(_)) |
+                 (IpVersionMode::V6Only, SocketAddr::V6(_)))
+    }
+}
+
+/// Check if the IPv6 multicast group is limited to a single link, so that the
+/// scope id of an interface is needed to send to it.
+pub fn is_link_scoped_group(group: &Ipv6Addr) -> bool {
+    group.segments()[0] & 0x000f <= 0x2
+}
+
+/// Build the address to send to the IPv6 multicast group through the interface
+/// with the given local address.
+///
+/// The scope id of the interface is only kept for link scoped groups.
+pub fn ipv6_group_addr(group: Ipv6Addr, port: u16, local_addr: &SocketAddrV6) -> SocketAddrV6 {
+    let scope_id = if is_link_scoped_group(&group) { local_addr.scope_id() } else { 0 };
+
+    SocketAddrV6::new(group, port, local_addr.flowinfo(), scope_id)
 }
 
 /// Accept a type implementing `ToSocketAddrs` and tries to extract the first address.
@@ -43,6 +76,50 @@ pub fn addr_from_trait<A: ToSocketAddrs>(addr: A) -> io::Result<SocketAddr> {
     }
 }
 
+/// Accept a type implementing `ToSocketAddrs` and tries to extract the first
+/// address of a version allowed by the given mode.
+pub fn addr_from_trait_with_mode<A: ToSocketAddrs>(addr: A, mode: &IpVersionMode) -> io::Result<SocketAddr> {
+    let mut sock_iter = addr.to_socket_addrs()?;
+
+    match sock_iter.find(|addr| mode.allows(addr)) {
+        Some(n) => Ok(n),
+        None => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "No SocketAddr Matches The IpVersionMode",
+        )),
+    }
+}
+
+/// Invoke the bind closure, retrying up to `retries` times with a short
+/// backoff while it fails because the address is still in use.
+///
+/// This covers restarts where the previous socket has not been released yet.
+pub fn retry_bind<F, T>(retries: u32, mut bind: F) -> io::Result<T>
+where
+    F: FnMut() -> io::Result<T>,
+{
+    let mut backoff = Duration::from_millis(BIND_RETRY_BACKOFF_MILLIS);
+
+    for attempt in 0.. {
+        match bind() {
+            Err(ref err) if err.kind() == ErrorKind::AddrInUse && attempt < retries => {
+                debug!("Bind failed with {}, retrying in {:?}", err, backoff);
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+            Err(ref err) if err.kind() == ErrorKind::AddrInUse => {
+                return Err(io::Error::new(
+                    ErrorKind::AddrInUse,
+                    format!("Address Still In Use After {} Attempts: {}", attempt + 1, err),
+                ));
+            }
+            result => return result,
+        }
+    }
+
+    unreachable!()
+}
+
 /// Bind to a `UdpSocket`, setting `SO_REUSEADDR` on the underlying socket before binding.
 pub fn bind_reuse<A: ToSocketAddrs>(local_addr: A) -> io::Result<UdpSocket> {
     let local_addr = addr_from_trait(local_addr)?;
@@ -58,7 +135,7 @@ pub fn bind_reuse<A: ToSocketAddrs>(local_addr: A) -> io::Result<UdpSocket> {
 }
 
 #[cfg(windows)]
-fn reuse_port(builder: &Socket) -> io::Result<()> {
+fn reuse_port(socket: &Socket) -> io::Result<()> {
     // Allow wildcards + specific to not overlap
     socket.set_reuse_address(true)?;
     Ok(())
@@ -68,11 +145,34 @@ fn reuse_port(builder: &Socket) -> io::Result<()> {
 fn reuse_port(socket: &Socket) -> io::Result<()> {
     // Allow wildcards + specific to not overlap
     socket.set_reuse_address(true)?;
-    // Allow multiple listeners on the same port
-    socket.set_reuse_port(true)?;
+    // Allow multiple listeners on the same port, where the system supports it.
+    // SO_REUSEADDR alone still lets multicast listeners share the port on most
+    // of the systems that do not.
+    if let Err(err) = socket.set_reuse_port(true) {
+        debug!("Falling back to SO_REUSEADDR only: {}", err);
+    }
     Ok(())
 }
 
+/// Get the IPv6 scope id of the interface with the given name.
+///
+/// Returns 0, the unspecified scope, if the interface is unknown.
+#[cfg(target_os = "linux")]
+pub fn scope_id(iface_name: &str) -> u32 {
+    match CString::new(iface_name) {
+        Ok(name) => unsafe { libc::if_nametoindex(name.as_ptr()) },
+        Err(_) => 0,
+    }
+}
+
+/// Get the IPv6 scope id of the interface with the given name.
+///
+/// Scope ids are not looked up on this platform, so this is always 0.
+#[cfg(not(target_os = "linux"))]
+pub fn scope_id(_iface_name: &str) -> u32 {
+    0
+}
+
 /// Join a multicast address on the current `UdpSocket`.
 pub fn join_multicast(sock: &UdpSocket, iface: &SocketAddr, mcast_addr: &IpAddr) -> io::Result<()> {
     match (iface, mcast_addr) {
@@ -100,6 +200,11 @@ pub fn leave_multicast(sock: &UdpSocket, iface_addr: &SocketAddr, mcast_addr: &S
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+    use std::io::{self, ErrorKind};
+    use std::net::SocketAddrV6;
+
+    use super::IpVersionMode;
 
     #[test]
     fn positive_addr_from_trait() {
@@ -111,4 +216,90 @@ mod tests {
     fn negative_addr_from_trait() {
         super::addr_from_trait("192.168.0.1").unwrap();
     }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn positive_scope_id_loopback() {
+        assert!(super::scope_id("lo") > 0);
+    }
+
+    #[test]
+    fn positive_scope_id_unknown() {
+        assert_eq!(super::scope_id("no such interface"), 0);
+    }
+
+    #[test]
+    fn positive_ipv6_group_addr_scope() {
+        let local_addr = SocketAddrV6::new("fe80::2".parse().unwrap(), 0, 0, 3);
+
+        let link_local = super::ipv6_group_addr("ff02::c".parse().unwrap(), 1900, &local_addr);
+        let site_local = super::ipv6_group_addr("ff05::c".parse().unwrap(), 1900, &local_addr);
+        let org_local = super::ipv6_group_addr("ff08::c".parse().unwrap(), 1900, &local_addr);
+
+        assert_eq!(link_local.scope_id(), 3);
+        assert_eq!(site_local.scope_id(), 0);
+        assert_eq!(org_local.scope_id(), 0);
+    }
+
+    #[test]
+    fn positive_addr_with_mode() {
+        let addrs = ["[::1]:1900".parse().unwrap(), "127.0.0.1:1900".parse().unwrap()];
+
+        assert_eq!(super::addr_from_trait_with_mode(&addrs[..], &IpVersionMode::V4Only).unwrap(), addrs[1]);
+        assert_eq!(super::addr_from_trait_with_mode(&addrs[..], &IpVersionMode::V6Only).unwrap(), addrs[0]);
+        assert_eq!(super::addr_from_trait_with_mode(&addrs[..], &IpVersionMode::Any).unwrap(), addrs[0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_v4_addr_with_v6_mode() {
+        super::addr_from_trait_with_mode("127.0.0.1:1900", &IpVersionMode::V6Only).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn negative_v6_addr_with_v4_mode() {
+        super::addr_from_trait_with_mode("[::1]:1900", &IpVersionMode::V4Only).unwrap();
+    }
+
+    /// Mock bind that fails with an address in use error for the first `failures` attempts.
+    fn flaky_bind(attempts: &Cell<u32>, failures: u32) -> io::Result<()> {
+        attempts.set(attempts.get() + 1);
+
+        if attempts.get() <= failures {
+            Err(io::Error::new(ErrorKind::AddrInUse, "mock address in use"))
+        } else {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn positive_retry_bind_transient_failure() {
+        let attempts = Cell::new(0);
+
+        super::retry_bind(3, || flaky_bind(&attempts, 2)).unwrap();
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[test]
+    fn negative_retry_bind_exhausted() {
+        let attempts = Cell::new(0);
+
+        let err = super::retry_bind(2, || flaky_bind(&attempts, 10)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AddrInUse);
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[test]
+    fn negative_retry_bind_other_error() {
+        let attempts = Cell::new(0);
+
+        let result: io::Result<()> = super::retry_bind(3, || {
+            attempts.set(attempts.get() + 1);
+            Err(io::Error::new(ErrorKind::PermissionDenied, "mock permission denied"))
+        });
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::PermissionDenied);
+        assert_eq!(attempts.get(), 1);
+    }
 }
@@ -1,39 +1,112 @@
 use std::io::{self, Error, ErrorKind};
-use std::net::{UdpSocket, SocketAddr};
+use std::net::{IpAddr, UdpSocket, SocketAddr};
 use std::fmt;
+use std::time::Instant;
 
 /// Maximum length for packets received on a `PacketReceiver`.
-pub const MAX_PCKT_LEN: usize = 1500;
+///
+/// Most SSDP messages fit in a single 1500 byte MTU, but devices with long
+/// `SERVER` strings or many headers are known to send larger datagrams.
+pub const MAX_PCKT_LEN: usize = 8192;
+
+/// Describes where a received packet came from and where it entered the local host.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PacketInfo {
+    /// Address of the sender of the packet.
+    pub src_addr: SocketAddr,
+    /// Local address of the socket the packet was read from.
+    pub local_addr: SocketAddr,
+    /// Index of the interface the packet arrived on, if the platform reports it.
+    pub if_index: Option<u32>,
+    /// Destination address of the packet, if the platform reports it.
+    ///
+    /// This is the multicast group for multicast packets.
+    pub dst_addr: Option<IpAddr>,
+    /// Monotonic time at which the packet was read from the socket.
+    pub arrived_at: Instant,
+}
 
 /// A `PacketReceiver` that abstracts over a network socket and reads full packets
 /// from the connection. Packets received from this connection are assumed to
-/// be no larger than what the typical MTU would be on a standard router.
+/// be no larger than a configured maximum length.
 ///
 /// See `net::packet::MAX_PCKT_LEN`.
-pub struct PacketReceiver(UdpSocket);
+pub struct PacketReceiver(UdpSocket, usize);
 
 impl PacketReceiver {
     /// Create a new PacketReceiver from the given UdpSocket.
+    ///
+    /// Where supported, the socket will be asked to report the interface each
+    /// packet arrives on.
     pub fn new(udp: UdpSocket) -> PacketReceiver {
-        PacketReceiver(udp)
+        PacketReceiver::with_max_len(udp, MAX_PCKT_LEN)
+    }
+
+    /// Create a new PacketReceiver as with `PacketReceiver::new`, accepting
+    /// packets of at most `max_len` bytes.
+    pub fn with_max_len(udp: UdpSocket, max_len: usize) -> PacketReceiver {
+        #[cfg(target_os = "linux")]
+        {
+            if let Err(err) = pktinfo::enable(&udp) {
+                debug!("Falling back to per socket interface attribution: {}", err);
+            }
+        }
+
+        PacketReceiver(udp, max_len)
     }
 
-    /// Receive a packet from the underlying connection.
-    pub fn recv_pckt(&self) -> io::Result<(Vec<u8>, SocketAddr)> {
-        let mut pckt_buf = vec![0u8; MAX_PCKT_LEN];
+    /// Receive a packet from the underlying connection along with where it came from.
+    ///
+    /// Packets longer than the maximum length are rejected with an error of
+    /// kind `InvalidData`, instead of being passed on cut short.
+    pub fn recv_pckt_info(&self) -> io::Result<(Vec<u8>, PacketInfo)> {
+        // One spare byte tells a packet of exactly the maximum length apart
+        // from one the socket had to cut off.
+        let mut pckt_buf = vec![0u8; self.1 + 1];
 
-        let (size, addr) = try!(self.0.recv_from(&mut pckt_buf));
+        let (size, info) = self.recv_into(&mut pckt_buf)?;
 
         // Check For Something That SHOULD NEVER Occur.
         if size > pckt_buf.len() {
             Err(Error::new(ErrorKind::Other, "UdpSocket Reported Receive Length Greater Than Buffer"))
+        } else if size > self.1 {
+            Err(Error::new(ErrorKind::InvalidData,
+                           format!("Packet From {} Is Larger Than {} Bytes", info.src_addr, self.1)))
         } else {
             // `truncate` does not reallocate the vec's backing storage
             pckt_buf.truncate(size);
 
-            Ok((pckt_buf, addr))
+            Ok((pckt_buf, info))
         }
     }
+
+    #[cfg(target_os = "linux")]
+    fn recv_into(&self, buf: &mut [u8]) -> io::Result<(usize, PacketInfo)> {
+        let (size, src_addr, if_index, dst_addr) = pktinfo::recv(&self.0, buf)?;
+
+        Ok((size,
+            PacketInfo {
+                src_addr,
+                local_addr: self.0.local_addr()?,
+                if_index,
+                dst_addr,
+                arrived_at: Instant::now(),
+            }))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn recv_into(&self, buf: &mut [u8]) -> io::Result<(usize, PacketInfo)> {
+        let (size, src_addr) = self.0.recv_from(buf)?;
+
+        Ok((size,
+            PacketInfo {
+                src_addr,
+                local_addr: self.0.local_addr()?,
+                if_index: None,
+                dst_addr: None,
+                arrived_at: Instant::now(),
+            }))
+    }
 }
 
 impl fmt::Display for PacketReceiver {
@@ -44,3 +117,165 @@ impl fmt::Display for PacketReceiver {
         }
     }
 }
+
+/// Receiving packets with `IP_PKTINFO` / `IPV6_PKTINFO` ancillary data.
+#[cfg(target_os = "linux")]
+mod pktinfo {
+    use std::io;
+    use std::mem;
+    use std::ptr;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, UdpSocket};
+    use std::os::unix::io::AsRawFd;
+
+    use libc;
+
+    /// Ask the socket to attach packet info to every received packet.
+    pub fn enable(udp: &UdpSocket) -> io::Result<()> {
+        let (level, name) = match udp.local_addr()? {
+            SocketAddr::V4(_) => (libc::IPPROTO_IP, libc::IP_PKTINFO),
+            SocketAddr::V6(_) => (libc::IPPROTO_IPV6, libc::IPV6_RECVPKTINFO),
+        };
+        let enable: libc::c_int = 1;
+
+        let result = unsafe {
+            libc::setsockopt(udp.as_raw_fd(),
+                             level,
+                             name,
+                             &enable as *const libc::c_int as *const libc::c_void,
+                             mem::size_of::<libc::c_int>() as libc::socklen_t)
+        };
+
+        if result == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Receive a packet into the buffer, returning its size, source, ingress
+    /// interface index and destination address.
+    pub fn recv(udp: &UdpSocket, buf: &mut [u8]) -> io::Result<(usize, SocketAddr, Option<u32>, Option<IpAddr>)> {
+        let mut src: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        // u64 elements keep the control buffer aligned for cmsghdr
+        let mut control = [0u64; 16];
+
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_name = &mut src as *mut libc::sockaddr_storage as *mut libc::c_void;
+        msg.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = mem::size_of_val(&control) as _;
+
+        let size = unsafe { libc::recvmsg(udp.as_raw_fd(), &mut msg, 0) };
+        if size < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let src_addr = socket_addr(&src)?;
+        let mut if_index = None;
+        let mut dst_addr = None;
+
+        unsafe {
+            let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+            while !cmsg.is_null() {
+                match ((*cmsg).cmsg_level, (*cmsg).cmsg_type) {
+                    (libc::IPPROTO_IP, libc::IP_PKTINFO) => {
+                        let info = ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const libc::in_pktinfo);
+
+                        if_index = Some(info.ipi_ifindex as u32);
+                        dst_addr = Some(IpAddr::V4(Ipv4Addr::from(u32::from_be(info.ipi_addr.s_addr))));
+                    }
+                    (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO) => {
+                        let info = ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const libc::in6_pktinfo);
+
+                        if_index = Some(info.ipi6_ifindex as u32);
+                        dst_addr = Some(IpAddr::V6(Ipv6Addr::from(info.ipi6_addr.s6_addr)));
+                    }
+                    _ => (),
+                }
+
+                cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+            }
+        }
+
+        Ok((size as usize, src_addr, if_index, dst_addr))
+    }
+
+    /// Convert a raw socket address filled in by the kernel.
+    fn socket_addr(storage: &libc::sockaddr_storage) -> io::Result<SocketAddr> {
+        match storage.ss_family as libc::c_int {
+            libc::AF_INET => {
+                let addr = unsafe { &*(storage as *const libc::sockaddr_storage as *const libc::sockaddr_in) };
+
+                Ok(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr)),
+                                                    u16::from_be(addr.sin_port))))
+            }
+            libc::AF_INET6 => {
+                let addr = unsafe { &*(storage as *const libc::sockaddr_storage as *const libc::sockaddr_in6) };
+
+                Ok(SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::from(addr.sin6_addr.s6_addr),
+                                                    u16::from_be(addr.sin6_port),
+                                                    addr.sin6_flowinfo,
+                                                    addr.sin6_scope_id)))
+            }
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Unknown Address Family")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+
+    use std::io::ErrorKind;
+
+    use super::PacketReceiver;
+
+    #[test]
+    fn positive_packet_info() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        send_sock.send_to(b"packet", recv_addr).unwrap();
+        let (bytes, info) = PacketReceiver::new(recv_sock).recv_pckt_info().unwrap();
+
+        assert_eq!(&bytes[..], &b"packet"[..]);
+        assert_eq!(info.src_addr, send_sock.local_addr().unwrap());
+        assert_eq!(info.local_addr, recv_addr);
+        if cfg!(target_os = "linux") {
+            assert_eq!(info.dst_addr, Some(recv_addr.ip()));
+            assert!(info.if_index.is_some());
+        }
+    }
+
+    #[test]
+    fn positive_large_packet() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let packet = vec![b'a'; 4000];
+
+        send_sock.send_to(&packet, recv_addr).unwrap();
+        let (bytes, _) = PacketReceiver::new(recv_sock).recv_pckt_info().unwrap();
+
+        assert_eq!(bytes, packet);
+    }
+
+    #[test]
+    fn negative_truncated_packet() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        send_sock.send_to(&[b'a'; 101], recv_addr).unwrap();
+        let err = PacketReceiver::with_max_len(recv_sock, 100).recv_pckt_info().unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
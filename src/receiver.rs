@@ -1,18 +1,76 @@
 //! Primitives for non-blocking SSDP message receiving.
 
+use std::cmp;
 use std::io;
 use std::result::Result;
-use std::thread;
-use std::sync::mpsc::{self, Receiver, Sender, TryRecvError, RecvError, Iter};
+use std::thread::{self, JoinHandle};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError, RecvError, RecvTimeoutError, Iter};
 use std::net::{UdpSocket, SocketAddr};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use SSDPResult;
-use net::packet::PacketReceiver;
+use net::packet::{PacketInfo, PacketReceiver};
+
+/// Interval at which receive threads check if their `SSDPReceiver` was dropped.
+const STOP_POLL_INTERVAL_MS: u64 = 50;
+
+/// Longest a `SharedReceiver` handle holds the receiver before letting others in.
+const SHARED_SLICE_MS: u64 = 50;
+
+/// Interval at which a `SharedReceiver` handle with a deadline retries a busy receiver.
+const SHARED_RETRY_MS: u64 = 5;
 
 /// Trait for constructing an object from some serialized SSDP message.
 pub trait FromRawSSDP: Sized {
     fn raw_ssdp(bytes: &[u8]) -> SSDPResult<Self>;
+
+    /// Construct an object from some serialized SSDP message that was received
+    /// in a packet described by the given info.
+    ///
+    /// By default the packet info is discarded.
+    fn raw_ssdp_with_info(bytes: &[u8], _info: &PacketInfo) -> SSDPResult<Self> {
+        Self::raw_ssdp(bytes)
+    }
+}
+
+/// Passes on messages that fail to parse as errors, instead of dropping them.
+///
+/// Receiving `SSDPResult<T>` rather than `T` makes misbehaving senders visible.
+impl<T: FromRawSSDP> FromRawSSDP for SSDPResult<T> {
+    fn raw_ssdp(bytes: &[u8]) -> SSDPResult<Self> {
+        Ok(T::raw_ssdp(bytes))
+    }
+
+    fn raw_ssdp_with_info(bytes: &[u8], info: &PacketInfo) -> SSDPResult<Self> {
+        Ok(T::raw_ssdp_with_info(bytes, info))
+    }
+}
+
+/// Predicate deciding whether a message received from the given address
+/// should be passed on by an `SSDPReceiver`.
+pub type SSDPFilter<T> = Arc<dyn Fn(&T, &SocketAddr) -> bool + Send + Sync>;
+
+/// Destination that received messages can be pushed into as they arrive.
+pub trait Sink<T> {
+    /// Push an item into the sink.
+    ///
+    /// Returns false if the sink can not accept any more items.
+    fn push(&mut self, item: T) -> bool;
+}
+
+impl<T> Sink<T> for Vec<T> {
+    fn push(&mut self, item: T) -> bool {
+        Vec::push(self, item);
+        true
+    }
+}
+
+impl<T> Sink<T> for Sender<T> {
+    fn push(&mut self, item: T) -> bool {
+        self.send(item).is_ok()
+    }
 }
 
 /// Iterator for an `SSDPReceiver`.
@@ -34,9 +92,144 @@ impl<T> Iterator for SSDPIter<T> {
     }
 }
 
+/// Handle for stopping an `SSDPReceiver` from another thread.
+///
+/// Once stopped, the receive threads exit within a short poll interval and
+/// any blocked `recv` or iterator on the receiver returns.
+#[derive(Clone, Debug)]
+pub struct StopHandle {
+    stop: Arc<AtomicBool>,
+}
+
+impl StopHandle {
+    /// Stop the receiver this handle belongs to.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+
+    /// Check if the receiver was stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+}
+
+/// Cloneable handle to an `SSDPReceiver` that several threads can pull values from.
+///
+/// Every value is handed to exactly one of the threads. Only one thread waits
+/// on the receiver at a time, and only for a short slice before letting the
+/// others take their turn. Once the receiver times out or is stopped, every
+/// handle sees it as disconnected.
+///
+/// The receiver is closed when the last handle is dropped.
+pub struct SharedReceiver<T> {
+    inner: Arc<Mutex<SSDPReceiver<T>>>,
+    stop: StopHandle,
+}
+
+impl<T> Clone for SharedReceiver<T> {
+    fn clone(&self) -> Self {
+        SharedReceiver {
+            inner: self.inner.clone(),
+            stop: self.stop.clone(),
+        }
+    }
+}
+
+impl<T> SharedReceiver<T> {
+    /// Get a handle that stops the shared receiver for all handles.
+    ///
+    /// This does not wait for a thread blocked in `recv`.
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop.clone()
+    }
+
+    /// Non-blocking method that attempts to read a value from the receiver.
+    ///
+    /// Returns `Ok(None)` while another thread is waiting on the receiver.
+    pub fn try_recv(&self) -> Result<Option<(T, SocketAddr)>, RecvError> {
+        match self.inner.try_lock() {
+            Ok(recv) => recv.try_recv(),
+            Err(TryLockError::Poisoned(err)) => err.into_inner().try_recv(),
+            Err(TryLockError::WouldBlock) => Ok(None),
+        }
+    }
+
+    /// Blocking method that reads a value from the receiver until one is available.
+    pub fn recv(&self) -> Result<(T, SocketAddr), RecvError> {
+        let slice = Duration::from_millis(SHARED_SLICE_MS);
+
+        loop {
+            match self.lock().recv_timeout(slice) {
+                Ok(item) => return Ok(item),
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => return Err(RecvError),
+            }
+        }
+    }
+
+    /// Blocking method that reads a value from the receiver, waiting at most
+    /// until the given deadline.
+    ///
+    /// While another thread is waiting on the receiver, this retries until the
+    /// deadline instead of queueing behind it.
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<(T, SocketAddr), RecvTimeoutError> {
+        let slice = Duration::from_millis(SHARED_SLICE_MS);
+        let retry = Duration::from_millis(SHARED_RETRY_MS);
+
+        loop {
+            let now = Instant::now();
+            let until = cmp::min(deadline, now + slice);
+            let result = match self.inner.try_lock() {
+                Ok(recv) => recv.recv_deadline(until),
+                Err(TryLockError::Poisoned(err)) => err.into_inner().recv_deadline(until),
+                Err(TryLockError::WouldBlock) => {
+                    thread::sleep(cmp::min(retry, deadline.saturating_duration_since(now)));
+                    Err(RecvTimeoutError::Timeout)
+                }
+            };
+
+            match result {
+                Err(RecvTimeoutError::Timeout) if Instant::now() < deadline => (),
+                result => return result,
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SSDPReceiver<T>> {
+        // A thread panicking with the lock held leaves the receiver itself intact
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Iterator for a `SharedReceiver`, ending once the receiver is disconnected.
+pub struct SharedIter<'a, T: 'a> {
+    recv: &'a SharedReceiver<T>,
+}
+
+impl<'a, T> Iterator for SharedIter<'a, T> {
+    type Item = (T, SocketAddr);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv.recv().ok()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SharedReceiver<T> {
+    type Item = (T, SocketAddr);
+    type IntoIter = SharedIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SharedIter { recv: self }
+    }
+}
+
 /// A non-blocking SSDP message receiver.
+///
+/// Dropping the receiver stops its receive threads and closes its sockets.
 pub struct SSDPReceiver<T> {
     recvr: Receiver<(T, SocketAddr)>,
+    stop: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
 }
 
 impl<T> SSDPReceiver<T>
@@ -49,45 +242,193 @@ impl<T> SSDPReceiver<T>
     /// Due to implementation details, none of the UdpSockets should be bound to
     /// the default route, 0.0.0.0, address.
     pub fn new(socks: Vec<UdpSocket>, time: Option<Duration>) -> io::Result<SSDPReceiver<T>> {
+        SSDPReceiver::with_filter(socks, time, Arc::new(|_: &T, _: &SocketAddr| true))
+    }
+
+    /// Construct a receiver that receives from a single, existing UdpSocket,
+    /// such as one inherited through socket activation.
+    ///
+    /// This changes the socket itself, which is seen through every other
+    /// handle to it, including those from `UdpSocket::try_clone`: its read
+    /// timeout is set to a short poll interval and, on Linux, `IP_PKTINFO` or
+    /// `IPV6_RECVPKTINFO` is enabled to record the interface of each packet.
+    pub fn from_socket(sock: UdpSocket, time: Option<Duration>) -> io::Result<SSDPReceiver<T>> {
+        SSDPReceiver::new(vec![sock], time)
+    }
+
+    /// Construct a receiver as with `SSDPReceiver::new`, only passing on the
+    /// objects for which the given filter returns true.
+    pub fn with_filter(socks: Vec<UdpSocket>,
+                       time: Option<Duration>,
+                       filter: SSDPFilter<T>)
+                       -> io::Result<SSDPReceiver<T>> {
+        SSDPReceiver::with_deadline(socks, time, None, filter)
+    }
+
+    /// Construct a receiver as with `SSDPReceiver::with_filter`, which is also
+    /// shut down once the deadline passes, even while packets keep arriving.
+    pub(crate) fn with_deadline(socks: Vec<UdpSocket>,
+                                time: Option<Duration>,
+                                deadline: Option<Instant>,
+                                filter: SSDPFilter<T>)
+                                -> io::Result<SSDPReceiver<T>> {
         let (send, recv) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
 
-        // Ensure `receive_packets` times out in the event the timeout packet is not received
+        // Wake `receive_packets` regularly so it can check for the timeout and for being stopped
+        let poll_interval = Duration::from_millis(STOP_POLL_INTERVAL_MS);
+        let read_timeout = time.map_or(poll_interval, |n| cmp::min(n, poll_interval));
         for sock in socks.iter() {
-            try!(sock.set_read_timeout(time));
+            sock.set_read_timeout(Some(read_timeout))?;
         }
 
         // Spawn Receiver Threads
-        spawn_receivers(socks, send);
+        let handles = spawn_receivers(socks, send, filter, time, deadline, &stop);
 
-        Ok(SSDPReceiver { recvr: recv })
+        Ok(SSDPReceiver {
+            recvr: recv,
+            stop,
+            handles,
+        })
     }
 }
 
 /// Spawn a number of receiver threads that will receive packets, forward the
 /// bytes on to T, and send successfully constructed objects through the sender.
-fn spawn_receivers<T>(socks: Vec<UdpSocket>, sender: Sender<(T, SocketAddr)>)
+fn spawn_receivers<T>(socks: Vec<UdpSocket>,
+                      sender: Sender<(T, SocketAddr)>,
+                      filter: SSDPFilter<T>,
+                      time: Option<Duration>,
+                      deadline: Option<Instant>,
+                      stop: &Arc<AtomicBool>)
+                      -> Vec<JoinHandle<()>>
     where T: FromRawSSDP + Send + 'static
 {
-    for sock in socks {
-        let pckt_recv = PacketReceiver::new(sock);
-        let sender = sender.clone();
+    socks.into_iter()
+        .map(|sock| {
+            let pckt_recv = PacketReceiver::new(sock);
+            let sender = sender.clone();
+            let filter = filter.clone();
+            let stop = stop.clone();
 
-        thread::spawn(move || {
-            receive_packets(pckt_recv, sender);
-        });
-    }
+            thread::spawn(move || {
+                receive_packets(pckt_recv, sender, filter, time, deadline, &stop);
+            })
+        })
+        .collect()
 }
 
 impl<T> SSDPReceiver<T> {
+    /// Get a handle that stops this receiver, such as from a supervising thread.
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle { stop: self.stop.clone() }
+    }
+
     /// Non-blocking method that attempts to read a value from the receiver.
-    pub fn try_recv(&self) -> Result<(T, SocketAddr), TryRecvError> {
-        self.recvr.try_recv()
+    ///
+    /// Returns `Ok(None)` if no value is pending, or an error once the receiver
+    /// has shut down and every value was read.
+    pub fn try_recv(&self) -> Result<Option<(T, SocketAddr)>, RecvError> {
+        match self.recvr.try_recv() {
+            Ok(item) => Ok(Some(item)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(RecvError),
+        }
     }
 
     /// Blocking method that reads a value from the receiver until one is available.
     pub fn recv(&self) -> Result<(T, SocketAddr), RecvError> {
         self.recvr.recv()
     }
+
+    /// Blocking method that reads a value from the receiver, waiting at most
+    /// for the given timeout.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<(T, SocketAddr), RecvTimeoutError> {
+        self.recvr.recv_timeout(timeout)
+    }
+
+    /// Blocking method that reads a value from the receiver, waiting at most
+    /// until the given deadline.
+    ///
+    /// Returns immediately with a timeout if the deadline has already passed
+    /// and no value is pending.
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<(T, SocketAddr), RecvTimeoutError> {
+        let now = Instant::now();
+
+        if deadline <= now {
+            return self.recvr.try_recv().map_err(|err| match err {
+                TryRecvError::Empty => RecvTimeoutError::Timeout,
+                TryRecvError::Disconnected => RecvTimeoutError::Disconnected,
+            });
+        }
+
+        self.recvr.recv_timeout(deadline - now)
+    }
+
+    /// Blocking method that pushes every value into the sink as it arrives,
+    /// until the receiver shuts down or the sink stops accepting values.
+    ///
+    /// Returns the number of values pushed into the sink.
+    pub fn drain_into<S>(&self, sink: &mut S) -> usize
+        where S: Sink<(T, SocketAddr)>
+    {
+        let mut count = 0;
+
+        for item in self {
+            if !sink.push(item) {
+                break;
+            }
+            count += 1;
+        }
+
+        count
+    }
+
+    /// Blocking method that collects values until `limit` of them arrived or
+    /// the timeout passed, whichever comes first, then closes the receiver.
+    ///
+    /// For a repeated multicast search, the limit counts the responses to
+    /// all repeats together. A timeout too large to be represented waits
+    /// until the receiver shuts down.
+    pub fn take_responses(self, limit: usize, timeout: Duration) -> Vec<(T, SocketAddr)> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut responses = Vec::new();
+
+        while responses.len() < limit {
+            let item = match deadline {
+                Some(deadline) => self.recv_deadline(deadline).ok(),
+                None => self.recv().ok(),
+            };
+
+            match item {
+                Some(item) => responses.push(item),
+                None => break,
+            }
+        }
+
+        responses
+    }
+
+    /// Turn this receiver into a handle that can be cloned and shared
+    /// between threads.
+    pub fn into_shared(self) -> SharedReceiver<T> {
+        let stop = self.stop_handle();
+
+        SharedReceiver {
+            inner: Arc::new(Mutex::new(self)),
+            stop,
+        }
+    }
+}
+
+impl<T> Drop for SSDPReceiver<T> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
 }
 
 impl<'a, T> IntoIterator for &'a SSDPReceiver<T> {
@@ -120,21 +461,48 @@ impl<T> IntoIterator for SSDPReceiver<T> {
 /// Receives bytes and attempts to construct a T which will be sent through the supplied channel.
 ///
 /// This should almost always be run in it's own thread.
-fn receive_packets<T>(recv: PacketReceiver, send: Sender<(T, SocketAddr)>)
+///
+/// Returns once no packet arrived for the given time, once the deadline
+/// passed, or once stopped.
+fn receive_packets<T>(recv: PacketReceiver,
+                      send: Sender<(T, SocketAddr)>,
+                      filter: SSDPFilter<T>,
+                      time: Option<Duration>,
+                      deadline: Option<Instant>,
+                      stop: &AtomicBool)
     where T: FromRawSSDP + Send
 {
     // TODO: Add logging to this function. Maybe forward sender IP Address along
     // so that we can do some checks when we parse the http.
+    let mut last_packet = Instant::now();
+
     loop {
+        if stop.load(Ordering::SeqCst) {
+            trace!("Receiver at {} stopped", recv);
+            return;
+        }
+
+        if deadline.is_some_and(|n| Instant::now() >= n) {
+            trace!("Receiver at {} reached its deadline", recv);
+            return;
+        }
+
         trace!("Waiting on packet at {}...", recv);
-        let (msg_bytes, addr) = match recv.recv_pckt() {
-            Ok((bytes, addr)) => (bytes, addr),
+        let (msg_bytes, info) = match recv.recv_pckt_info() {
+            Ok((bytes, info)) => (bytes, info),
             // Unix returns WouldBlock on timeout while Windows returns TimedOut
             Err(ref err) if err.kind() == io::ErrorKind::WouldBlock ||
                             err.kind() == io::ErrorKind::TimedOut => {
-                // We have waited for at least the desired timeout (or possibly longer)
-                trace!("Receiver at {} timed out", recv);
-                return;
+                if time.is_some_and(|n| last_packet.elapsed() >= n) {
+                    // We have waited for at least the desired timeout (or possibly longer)
+                    trace!("Receiver at {} timed out", recv);
+                    return;
+                }
+                continue;
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::InvalidData => {
+                debug!("Dropping packet at {}: {}", recv, err);
+                continue;
             }
             Err(_) => {
                 continue;
@@ -142,13 +510,285 @@ fn receive_packets<T>(recv: PacketReceiver, send: Sender<(T, SocketAddr)>)
         };
 
         trace!("Received packet with {} bytes", msg_bytes.len());
+        last_packet = Instant::now();
 
-        // Unwrap Will Cause A Panic If Receiver Hung Up Which Is Desired
-        match T::raw_ssdp(&msg_bytes[..]) {
-            Ok(n) => send.send((n, addr)).unwrap(),
+        let addr = info.src_addr;
+        match T::raw_ssdp_with_info(&msg_bytes[..], &info) {
+            Ok(ref n) if !filter(n, &addr) => {
+                trace!("Filtered message from {}", addr);
+                continue;
+            }
+            // The receiver hung up, nobody is left to read messages
+            Ok(n) => {
+                if send.send((n, addr)).is_err() {
+                    return;
+                }
+            }
             Err(_) => {
                 continue;
             }
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{SocketAddr, UdpSocket};
+    use std::sync::mpsc::{self, RecvError, RecvTimeoutError};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use SSDPResult;
+    use message::SearchResponse;
+    use super::{FromRawSSDP, SSDPReceiver};
+
+    struct Raw(Vec<u8>);
+
+    impl FromRawSSDP for Raw {
+        fn raw_ssdp(bytes: &[u8]) -> SSDPResult<Raw> {
+            Ok(Raw(bytes.to_vec()))
+        }
+    }
+
+    fn receiver_with_packets(packets: &[&[u8]]) -> SSDPReceiver<Raw> {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let receiver = SSDPReceiver::new(vec![recv_sock], Some(Duration::from_millis(200))).unwrap();
+        for packet in packets {
+            send_sock.send_to(packet, recv_addr).unwrap();
+        }
+
+        receiver
+    }
+
+    #[test]
+    fn positive_drain_into_vec() {
+        let receiver = receiver_with_packets(&[b"first", b"second"]);
+        let mut sink: Vec<(Raw, SocketAddr)> = Vec::new();
+
+        assert_eq!(receiver.drain_into(&mut sink), 2);
+
+        let packets: Vec<&[u8]> = sink.iter().map(|(Raw(bytes), _)| &bytes[..]).collect();
+        assert_eq!(packets, vec![&b"first"[..], &b"second"[..]]);
+    }
+
+    #[test]
+    fn positive_drain_into_sender() {
+        let receiver = receiver_with_packets(&[b"first", b"second"]);
+        let (mut send, recv) = mpsc::channel();
+
+        assert_eq!(receiver.drain_into(&mut send), 2);
+        assert_eq!(recv.iter().take(2).count(), 2);
+    }
+
+    #[test]
+    fn positive_drop_closes_sockets() {
+        for _ in 0..20 {
+            let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let addr = sock.local_addr().unwrap();
+
+            let receiver = SSDPReceiver::<Raw>::new(vec![sock], None).unwrap();
+            drop(receiver);
+
+            // Binding without address reuse only succeeds once the socket is closed
+            UdpSocket::bind(addr).unwrap();
+        }
+    }
+
+    #[test]
+    fn positive_parse_errors_received() {
+        let recv_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let recv_addr = recv_sock.local_addr().unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let receiver = SSDPReceiver::<SSDPResult<SearchResponse>>::new(vec![recv_sock],
+                                                                       Some(Duration::from_millis(200)))
+            .unwrap();
+
+        send_sock.send_to(b"garbage", recv_addr).unwrap();
+        send_sock.send_to(b"HTTP/1.1 200 OK\r\n\r\n", recv_addr).unwrap();
+
+        let results: Vec<bool> = receiver.into_iter().map(|(result, _)| result.is_ok()).collect();
+        assert_eq!(results, vec![false, true]);
+    }
+
+    #[test]
+    fn positive_try_recv_empty() {
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let receiver = SSDPReceiver::<Raw>::new(vec![sock], None).unwrap();
+
+        assert!(receiver.try_recv().unwrap().is_none());
+    }
+
+    #[test]
+    fn positive_try_recv_value() {
+        let receiver = receiver_with_packets(&[b"first"]);
+        thread::sleep(Duration::from_millis(100));
+
+        let (Raw(bytes), _) = receiver.try_recv().unwrap().unwrap();
+        assert_eq!(&bytes[..], b"first");
+    }
+
+    #[test]
+    fn negative_try_recv_disconnected() {
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let receiver = SSDPReceiver::<Raw>::new(vec![sock], Some(Duration::from_millis(50))).unwrap();
+        thread::sleep(Duration::from_millis(300));
+
+        assert_eq!(receiver.try_recv().err(), Some(RecvError));
+    }
+
+    #[test]
+    fn positive_recv_deadline() {
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let receiver = SSDPReceiver::<Raw>::new(vec![sock], None).unwrap();
+        let start = Instant::now();
+
+        let result = receiver.recv_deadline(start + Duration::from_millis(100));
+
+        assert_eq!(result.err(), Some(RecvTimeoutError::Timeout));
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(receiver.recv_deadline(start).err(), Some(RecvTimeoutError::Timeout));
+    }
+
+    #[test]
+    fn positive_recv_deadline_value() {
+        let receiver = receiver_with_packets(&[b"first"]);
+
+        let (Raw(bytes), _) = receiver.recv_deadline(Instant::now() + Duration::from_secs(2)).unwrap();
+        assert_eq!(&bytes[..], &b"first"[..]);
+    }
+
+    #[test]
+    fn positive_take_responses_limit() {
+        let receiver = receiver_with_packets(&[b"first", b"second", b"third"]);
+        let start = Instant::now();
+
+        let responses = receiver.take_responses(2, Duration::from_secs(5));
+
+        let packets: Vec<&[u8]> = responses.iter().map(|(Raw(bytes), _)| &bytes[..]).collect();
+        assert_eq!(packets, vec![&b"first"[..], &b"second"[..]]);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn positive_take_responses_timeout() {
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send_sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        send_sock.send_to(b"first", sock.local_addr().unwrap()).unwrap();
+        let receiver = SSDPReceiver::<Raw>::new(vec![sock], None).unwrap();
+        let start = Instant::now();
+
+        let responses = receiver.take_responses(3, Duration::from_millis(200));
+
+        assert_eq!(responses.len(), 1);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn positive_take_responses_unbounded() {
+        let receiver = receiver_with_packets(&[b"first", b"second"]);
+
+        let responses = receiver.take_responses(usize::MAX, Duration::new(u64::MAX, 0));
+
+        assert_eq!(responses.len(), 2);
+    }
+
+    #[test]
+    fn positive_shared_receiver() {
+        let receiver = receiver_with_packets(&[b"first", b"second", b"third", b"fourth"]).into_shared();
+
+        let workers: Vec<_> = (0..3)
+            .map(|_| {
+                let receiver = receiver.clone();
+                thread::spawn(move || receiver.into_iter().count())
+            })
+            .collect();
+        drop(receiver);
+
+        let total: usize = workers.into_iter().map(|worker| worker.join().unwrap()).sum();
+        assert_eq!(total, 4);
+    }
+
+    #[test]
+    fn positive_shared_receiver_stop() {
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let receiver = SSDPReceiver::<Raw>::new(vec![sock], None).unwrap().into_shared();
+        let handle = receiver.stop_handle();
+
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                let receiver = receiver.clone();
+                thread::spawn(move || receiver.recv().is_err())
+            })
+            .collect();
+        handle.stop();
+
+        assert!(workers.into_iter().all(|worker| worker.join().unwrap()));
+    }
+
+    #[test]
+    fn positive_shared_receiver_stop_while_blocked() {
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let receiver = SSDPReceiver::<Raw>::new(vec![sock], None).unwrap().into_shared();
+
+        let worker = {
+            let receiver = receiver.clone();
+            thread::spawn(move || receiver.recv().is_err())
+        };
+        thread::sleep(Duration::from_millis(100));
+
+        assert!(receiver.try_recv().unwrap().is_none());
+        receiver.stop_handle().stop();
+
+        assert!(worker.join().unwrap());
+    }
+
+    #[test]
+    fn positive_shared_receiver_deadline_while_blocked() {
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let receiver = SSDPReceiver::<Raw>::new(vec![sock], None).unwrap().into_shared();
+
+        let worker = {
+            let receiver = receiver.clone();
+            thread::spawn(move || receiver.recv().is_err())
+        };
+        thread::sleep(Duration::from_millis(100));
+
+        let start = Instant::now();
+        let result = receiver.recv_deadline(start + Duration::from_millis(200));
+
+        assert_eq!(result.err(), Some(RecvTimeoutError::Timeout));
+        assert!(start.elapsed() < Duration::from_millis(400));
+
+        receiver.stop_handle().stop();
+        assert!(worker.join().unwrap());
+    }
+
+    #[test]
+    fn positive_stop_handle() {
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let receiver = SSDPReceiver::<Raw>::new(vec![sock], None).unwrap();
+        let handle = receiver.stop_handle();
+
+        let listener = thread::spawn(move || receiver.into_iter().count());
+        thread::sleep(Duration::from_millis(100));
+
+        assert!(!handle.is_stopped());
+        handle.stop();
+
+        assert_eq!(listener.join().unwrap(), 0);
+        assert!(handle.is_stopped());
+    }
+
+    #[test]
+    fn positive_drain_into_closed_sender() {
+        let receiver = receiver_with_packets(&[b"first", b"second"]);
+        let (mut send, recv) = mpsc::channel();
+        drop(recv);
+
+        assert_eq!(receiver.drain_into(&mut send), 0);
+    }
+}